@@ -0,0 +1,382 @@
+use crate::database::is_valid_name;
+use crate::parser::{
+    split_table_name, Column, Comparison, Const, Join, OrderBy, Query, Value, ValueTest,
+};
+
+/// Represents a value of a condition being built: a column, a constant or a parameter.
+///
+/// Numbers and strings convert into constants, so `col("movies.title").eq("Heat")` compares
+/// the column with the string `'Heat'`.
+#[derive(Debug, Clone)]
+pub enum Operand {
+    /// A column, named with its table (`movies.title`).
+    Column(String),
+    Const(Const),
+    /// A parameter, numbered from 1, bound when the query is executed.
+    Param(usize),
+}
+
+/// Refers to a column in a condition.
+///
+/// # Arguments
+///
+/// * `name` - The name of the column, qualified by its table (`movies.title`).
+pub fn col(name: &str) -> Operand {
+    Operand::Column(name.to_string())
+}
+
+/// Refers to a parameter in a condition, as `$number` does in SQL.
+///
+/// # Arguments
+///
+/// * `number` - The number of the parameter, from 1.
+pub fn param(number: usize) -> Operand {
+    Operand::Param(number)
+}
+
+impl Operand {
+    /// Builds the condition `self = other`.
+    pub fn eq(self, other: impl Into<Operand>) -> Condition {
+        self.compare(Comparison::Eq, other)
+    }
+
+    /// Builds the condition `self <> other`.
+    pub fn ne(self, other: impl Into<Operand>) -> Condition {
+        self.compare(Comparison::Ne, other)
+    }
+
+    /// Builds the condition `self > other`.
+    pub fn gt(self, other: impl Into<Operand>) -> Condition {
+        self.compare(Comparison::Gt, other)
+    }
+
+    /// Builds the condition `self < other`.
+    pub fn lt(self, other: impl Into<Operand>) -> Condition {
+        self.compare(Comparison::Lt, other)
+    }
+
+    /// Builds the condition `self >= other`.
+    pub fn ge(self, other: impl Into<Operand>) -> Condition {
+        self.compare(Comparison::Ge, other)
+    }
+
+    /// Builds the condition `self <= other`.
+    pub fn le(self, other: impl Into<Operand>) -> Condition {
+        self.compare(Comparison::Le, other)
+    }
+
+    /// Builds a condition comparing `self` with another operand.
+    fn compare(self, comparison: Comparison, other: impl Into<Operand>) -> Condition {
+        Condition {
+            left: self,
+            comparison,
+            right: other.into(),
+        }
+    }
+
+    /// Converts the operand to a value of the AST.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the value, or a message if a column is not qualified by its table.
+    fn to_value(&self) -> Result<Value, String> {
+        match self {
+            Operand::Column(name) => Ok(Value::Column(column(name)?)),
+            Operand::Const(c) => Ok(Value::Const(c.clone())),
+            Operand::Param(0) => Err("Parameters are numbered from 1".to_string()),
+            Operand::Param(number) => Ok(Value::Param(*number)),
+        }
+    }
+}
+
+impl<T: Into<Const>> From<T> for Operand {
+    fn from(value: T) -> Self {
+        Operand::Const(value.into())
+    }
+}
+
+/// Represents a condition comparing two operands, for a `JOIN` or a `WHERE` clause.
+#[derive(Debug, Clone)]
+pub struct Condition {
+    left: Operand,
+    comparison: Comparison,
+    right: Operand,
+}
+
+impl Condition {
+    /// Converts the condition to a value test of the AST.
+    fn to_value_test(&self) -> Result<ValueTest, String> {
+        Ok(ValueTest {
+            left: self.left.to_value()?,
+            comparison: self.comparison.clone(),
+            right: self.right.to_value()?,
+        })
+    }
+}
+
+/// Builds a `Query` step by step, as an alternative to parsing SQL text.
+///
+/// The query is the one `parser::parse_query` returns for the equivalent SQL, and can be run
+/// with `PreparedStatement::from(query)` or `engine::View::execute`, which check its tables and
+/// columns against the database.
+///
+/// ```
+/// use simple_sql_engine::builder::{col, QueryBuilder};
+///
+/// let query = QueryBuilder::select(["movies.title", "actors.name"])
+///     .from("movies")
+///     .join("actors_in_movies", col("actors_in_movies.movieID").eq(col("movies.id")))
+///     .join("actors", col("actors.id").eq(col("actors_in_movies.actorID")))
+///     .filter(col("movies.cert").ge(15))
+///     .order_by("movies.title")
+///     .limit(10)
+///     .build()?;
+/// # Ok::<(), String>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QueryBuilder {
+    select: Vec<String>,
+    from: Option<String>,
+    joins: Vec<(String, Condition)>,
+    where_clause: Option<Condition>,
+    order_by: Vec<(String, bool)>,
+    limit: Option<usize>,
+}
+
+impl QueryBuilder {
+    /// Starts a query selecting columns.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - The names of the columns, qualified by their tables (`movies.title`).
+    pub fn select<I, S>(columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        QueryBuilder {
+            select: columns.into_iter().map(Into::into).collect(),
+            ..QueryBuilder::default()
+        }
+    }
+
+    /// Sets the table the query reads from.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table, which may be qualified by an attached database.
+    pub fn from(mut self, table_name: &str) -> Self {
+        self.from = Some(table_name.to_string());
+        self
+    }
+
+    /// Adds a `JOIN` clause.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the joined table.
+    /// * `on` - The condition rows of the table are joined on, which compares two columns.
+    pub fn join(mut self, table_name: &str, on: Condition) -> Self {
+        self.joins.push((table_name.to_string(), on));
+        self
+    }
+
+    /// Sets the condition of the `WHERE` clause, replacing any condition set before.
+    ///
+    /// # Arguments
+    ///
+    /// * `condition` - The condition the rows must satisfy.
+    pub fn filter(mut self, condition: Condition) -> Self {
+        self.where_clause = Some(condition);
+        self
+    }
+
+    /// Adds a column to sort the rows by, from the least value.
+    ///
+    /// # Arguments
+    ///
+    /// * `column` - The name of the column, qualified by its table.
+    pub fn order_by(mut self, column: &str) -> Self {
+        self.order_by.push((column.to_string(), false));
+        self
+    }
+
+    /// Adds a column to sort the rows by, from the greatest value.
+    ///
+    /// # Arguments
+    ///
+    /// * `column` - The name of the column, qualified by its table.
+    pub fn order_by_desc(mut self, column: &str) -> Self {
+        self.order_by.push((column.to_string(), true));
+        self
+    }
+
+    /// Sets the greatest number of rows to return.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The number of rows.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Builds the query.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the query, or a message describing why it is invalid: no columns
+    /// or table, a name that SQL could not hold, a column not qualified by its table, or a join
+    /// condition that does not compare two columns.
+    pub fn build(self) -> Result<Query, String> {
+        if self.select.is_empty() {
+            return Err("A query must select at least one column".to_string());
+        }
+        let from = self.from.ok_or("A query must read from a table")?;
+        let (database, from) = table_name(&from)?;
+
+        let joins = self
+            .joins
+            .iter()
+            .map(|(name, on)| {
+                let (database, table_name) = table_name(name)?;
+                let on = on.to_value_test()?;
+                if !matches!((&on.left, &on.right), (Value::Column(_), Value::Column(_))) {
+                    return Err(format!("The join of {} must compare two columns", name));
+                }
+                Ok(Join {
+                    database,
+                    table_name,
+                    on,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Query {
+            select: self
+                .select
+                .iter()
+                .map(|name| column(name))
+                .collect::<Result<_, _>>()?,
+            database,
+            from,
+            joins,
+            where_clause: self
+                .where_clause
+                .as_ref()
+                .map(Condition::to_value_test)
+                .transpose()?,
+            order_by: self
+                .order_by
+                .iter()
+                .map(|(name, descending)| {
+                    Ok(OrderBy {
+                        column: column(name)?,
+                        descending: *descending,
+                    })
+                })
+                .collect::<Result<_, String>>()?,
+            limit: self.limit,
+        })
+    }
+}
+
+/// Checks that a table name, which may be qualified by a database name, can be written in SQL,
+/// and splits it as the parser does.
+fn table_name(name: &str) -> Result<(Option<String>, String), String> {
+    let (database, table_name) = split_table_name(name);
+    if is_valid_name(&table_name) && database.as_deref().is_none_or(is_valid_name) {
+        Ok((database, table_name))
+    } else {
+        Err(format!("Invalid table name {}", name))
+    }
+}
+
+/// Splits the name of a column qualified by its table, as the parser does.
+fn column(name: &str) -> Result<Column, String> {
+    let (table, column_name) = name
+        .rsplit_once('.')
+        .ok_or_else(|| format!("Column {} must be qualified by its table", name))?;
+    let (database, table_name) = table_name(table)?;
+    if !is_valid_name(column_name) {
+        return Err(format!("Invalid column name {}", name));
+    }
+    Ok(Column {
+        database,
+        table_name,
+        column_name: column_name.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database;
+    use crate::engine::View;
+    use crate::parser::parse_query;
+
+    #[test]
+    fn test_build() {
+        let query = QueryBuilder::select(["movies.title", "actors.name"])
+            .from("movies")
+            .join(
+                "actors_in_movies",
+                col("actors_in_movies.movieID").eq(col("movies.id")),
+            )
+            .join(
+                "actors",
+                col("actors.id").eq(col("actors_in_movies.actorID")),
+            )
+            .filter(col("movies.title").ne("It's"))
+            .order_by_desc("movies.cert")
+            .order_by("actors.name")
+            .limit(3)
+            .build()
+            .unwrap();
+        assert_eq!(
+            query,
+            parse_query(
+                "SELECT movies.title, actors.name FROM movies \
+                 JOIN actors_in_movies ON actors_in_movies.movieID = movies.id \
+                 JOIN actors ON actors.id = actors_in_movies.actorID \
+                 WHERE movies.title <> 'It''s' ORDER BY movies.cert DESC, actors.name ASC LIMIT 3"
+            )
+            .unwrap()
+        );
+
+        let db = database::load_database("database/test_data.json").unwrap();
+        let view = View::execute(query, &db).unwrap();
+        assert_eq!(view.rows.len(), 3);
+
+        let query = QueryBuilder::select(["music.albums.title"])
+            .from("music.albums")
+            .filter(param(1).lt(col("music.albums.rating")))
+            .build()
+            .unwrap();
+        assert_eq!(
+            query,
+            parse_query(
+                "SELECT music.albums.title FROM music.albums WHERE $1 < music.albums.rating"
+            )
+            .unwrap()
+        );
+
+        for builder in [
+            QueryBuilder::select(Vec::<String>::new()).from("movies"),
+            QueryBuilder::select(["movies.title"]),
+            QueryBuilder::select(["title"]).from("movies"),
+            QueryBuilder::select(["movies.title"]).from("my movies"),
+            QueryBuilder::select(["movies.title"])
+                .from("movies")
+                .join("actors", col("actors.id").eq(1)),
+            QueryBuilder::select(["movies.title"])
+                .from("movies")
+                .filter(col("movies.id").eq(param(0))),
+            QueryBuilder::select(["movies.title"])
+                .from("movies")
+                .order_by("movies.title DESC"),
+        ] {
+            assert!(builder.clone().build().is_err(), "{:?}", builder);
+        }
+    }
+}
@@ -0,0 +1,818 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io;
+
+use serde::de::DeserializeOwned;
+
+use crate::database::{load_database_with_mode, DataType, Database, LoadMode};
+use crate::engine::{self, Outcome, View};
+use crate::parser::{self, Column, Const, Query, Statement};
+use crate::provider::TableProvider;
+
+/// Represents an error returned by the public API.
+#[derive(Debug)]
+pub enum Error {
+    /// The database could not be read or written.
+    Io(io::Error),
+    /// The SQL text is not valid.
+    Syntax(&'static str),
+    /// A statement could not be executed.
+    Execution(String),
+    /// The parameters of a statement skip a number, or the constants bound to it do not match
+    /// its parameters.
+    Parameter(String),
+    /// A value of a result row could not be read as the type asked for.
+    Column(String),
+}
+
+impl fmt::Display for Error {
+    /// Formats the error for display to a user, prefixing syntax errors.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Syntax(e) => write!(f, "Syntax error: {}", e),
+            Error::Execution(e) | Error::Parameter(e) | Error::Column(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// Represents an open database that statements are executed against.
+#[derive(Debug)]
+pub struct Connection {
+    database: Database,
+}
+
+impl Connection {
+    /// Creates a connection to a new, empty database that is not written to a file.
+    pub fn new() -> Self {
+        Connection {
+            database: Database::new(),
+        }
+    }
+
+    /// Opens a database file or directory, skipping malformed tables and rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the database file or directory.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the connection, or the error that stopped the database loading.
+    pub fn open(path: &str) -> Result<Self, Error> {
+        Connection::open_with_mode(path, LoadMode::Lenient)
+    }
+
+    /// Opens a database file or directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the database file or directory.
+    /// * `mode` - How malformed tables and rows are handled.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the connection, or the error that stopped the database loading.
+    pub fn open_with_mode(path: &str, mode: LoadMode) -> Result<Self, Error> {
+        Ok(Connection {
+            database: load_database_with_mode(path, mode)?,
+        })
+    }
+
+    /// Gets the database, with the warnings and diagnostics found while loading it.
+    pub fn database(&self) -> &Database {
+        &self.database
+    }
+
+    /// Allows statements to change a database whose malformed tables or rows were skipped
+    /// when loading it, which drops them from its file, as `Database::discard_skipped` does.
+    pub fn discard_skipped(&mut self) {
+        self.database.discard_skipped();
+    }
+
+    /// Registers a table provider as a read-only table, which queries can read and join with
+    /// the tables of the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table.
+    /// * `provider` - The provider of the rows of the table.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or an error if a table already has the name.
+    pub fn register_table(
+        &mut self,
+        table_name: &str,
+        provider: impl TableProvider + 'static,
+    ) -> Result<(), Error> {
+        self.database
+            .register_table(table_name, provider)
+            .map_err(Error::Execution)
+    }
+
+    /// Parses a single SQL statement, so it can be executed any number of times.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The statement, optionally terminated by `;`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the prepared statement, or a syntax error, or a parameter error if
+    /// its parameters skip a number.
+    pub fn prepare(&self, sql: &str) -> Result<PreparedStatement, Error> {
+        let statement = parser::parse_statement(sql).map_err(Error::Syntax)?;
+        statement.check_parameters().map_err(Error::Parameter)?;
+        Ok(PreparedStatement { statement })
+    }
+
+    /// Parses a script of SQL statements separated by `;`.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The script.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the prepared statements, in order, or a syntax error, or a
+    /// parameter error if the parameters of a statement skip a number.
+    pub fn prepare_all(&self, sql: &str) -> Result<Vec<PreparedStatement>, Error> {
+        let statements = parser::parse_statements(sql).map_err(Error::Syntax)?;
+        statements
+            .into_iter()
+            .map(|statement| {
+                statement.check_parameters().map_err(Error::Parameter)?;
+                Ok(PreparedStatement { statement })
+            })
+            .collect()
+    }
+
+    /// Executes a single SQL statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The statement, optionally terminated by `;`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the outcome of the statement, or the error that stopped it.
+    pub fn execute(&mut self, sql: &str) -> Result<Outcome, Error> {
+        self.prepare(sql)?.execute(self)
+    }
+
+    /// Runs a single SQL query.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The query, optionally terminated by `;`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the rows of the result, or the error that stopped the query.
+    pub fn query(&mut self, sql: &str) -> Result<Rows, Error> {
+        self.prepare(sql)?.query(self)
+    }
+}
+
+impl Default for Connection {
+    fn default() -> Self {
+        Connection::new()
+    }
+}
+
+impl From<Database> for Connection {
+    fn from(database: Database) -> Self {
+        Connection { database }
+    }
+}
+
+/// Represents a parsed SQL statement that can be executed any number of times.
+#[derive(Debug, Clone)]
+pub struct PreparedStatement {
+    statement: Statement,
+}
+
+impl PreparedStatement {
+    /// Gets the parsed statement.
+    pub fn statement(&self) -> &Statement {
+        &self.statement
+    }
+
+    /// Counts the parameters (`?` or `$1`) of the statement, which must be bound before it is
+    /// executed.
+    pub fn parameter_count(&self) -> usize {
+        self.statement.parameter_count()
+    }
+
+    /// Binds constants to the parameters of the statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The constants, where the first is bound to parameter 1 (`$1`). Numbers,
+    ///   strings and options convert into constants with `into()`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the statement with the constants in place of its parameters, or an
+    /// error if the number of constants does not match the number of parameters.
+    pub fn bind(&self, params: &[Const]) -> Result<PreparedStatement, Error> {
+        let statement = self.statement.bind(params).map_err(Error::Parameter)?;
+        Ok(PreparedStatement { statement })
+    }
+
+    /// Checks whether the statement is a query, whose rows can be read with `query`.
+    pub fn is_query(&self) -> bool {
+        matches!(self.statement, Statement::Select(_))
+    }
+
+    /// Executes the statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection` - The connection to execute the statement on.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the outcome of the statement, or the error that stopped it.
+    pub fn execute(&self, connection: &mut Connection) -> Result<Outcome, Error> {
+        engine::execute_statement(self.statement.clone(), &mut connection.database)
+            .map_err(Error::Execution)
+    }
+
+    /// Runs the statement, which must be a query.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection` - The connection to run the query on.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the rows of the result, or the error that stopped the query.
+    pub fn query(&self, connection: &mut Connection) -> Result<Rows, Error> {
+        if !matches!(self.statement, Statement::Select(_)) {
+            return Err(Error::Execution("The statement is not a query".to_string()));
+        }
+        match self.execute(connection)? {
+            Outcome::Rows(view) => Ok(Rows::new(&view, &connection.database)),
+            _ => unreachable!("A query always produces rows"),
+        }
+    }
+}
+
+/// Prepares a query made with `builder::QueryBuilder`, so it can be bound and executed like a
+/// query parsed from SQL.
+impl From<Query> for PreparedStatement {
+    fn from(query: Query) -> Self {
+        PreparedStatement {
+            statement: Statement::Select(query),
+        }
+    }
+}
+
+/// Describes a column of the result of a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultColumn {
+    /// The name of the column in the result, such as `movies.title`.
+    pub name: String,
+    /// The table the column belongs to, named as the query reads it, which may be qualified by
+    /// a database (`music.albums`).
+    pub table_name: String,
+    /// The name of the column in its table, such as `title`.
+    pub column_name: String,
+    /// The type declared for the column, or else the type of all its values in the result.
+    /// `None` if the result has no values of the column or values of both types.
+    pub data_type: Option<DataType>,
+}
+
+/// Represents the rows of the result of a query, with their values in the order of the
+/// selected columns.
+#[derive(Debug, Clone)]
+pub struct Rows {
+    columns: Vec<ResultColumn>,
+    /// The name of each column when a row is deserialized, as given by `field_names`.
+    fields: Vec<String>,
+    rows: Vec<Vec<Const>>,
+}
+
+impl Rows {
+    /// Builds the result of a query from the rows produced by the engine.
+    ///
+    /// # Arguments
+    ///
+    /// * `view` - The view produced by the query, whose columns are resolved to the tables
+    ///   the query reads.
+    /// * `database` - The database the query ran on, whose schemas give the column types.
+    fn new(view: &View, database: &Database) -> Self {
+        let names: Vec<String> = view.columns.iter().map(Column::qualified_name).collect();
+        let rows: Vec<Vec<Const>> = view
+            .rows
+            .iter()
+            .map(|row| {
+                names
+                    .iter()
+                    .map(|name| {
+                        row.get(name)
+                            .and_then(parser::Value::get_const)
+                            .unwrap_or(Const::Null)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let columns: Vec<ResultColumn> = view
+            .columns
+            .iter()
+            .zip(names)
+            .enumerate()
+            .map(|(i, (column, name))| ResultColumn {
+                data_type: declared_type(database, &column.qualified_table(), &column.column_name)
+                    .or_else(|| result_type(rows.iter().map(|row| &row[i]))),
+                name,
+                table_name: column.qualified_table(),
+                column_name: column.column_name.clone(),
+            })
+            .collect();
+
+        Rows {
+            fields: field_names(&columns),
+            columns,
+            rows,
+        }
+    }
+
+    /// Gets the columns of the result.
+    pub fn columns(&self) -> &[ResultColumn] {
+        &self.columns
+    }
+
+    /// Gets the number of rows.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Checks whether the result has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Gets a row of the result.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The position of the row.
+    pub fn get(&self, index: usize) -> Option<ResultRow<'_>> {
+        self.rows.get(index).map(|values| ResultRow {
+            columns: &self.columns,
+            fields: &self.fields,
+            values,
+        })
+    }
+
+    /// Iterates over the rows of the result.
+    pub fn iter(&self) -> impl Iterator<Item = ResultRow<'_>> {
+        self.rows.iter().map(|values| ResultRow {
+            columns: &self.columns,
+            fields: &self.fields,
+            values,
+        })
+    }
+
+    /// Deserializes every row of the result, as described by `ResultRow::deserialize`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the deserialized rows, or an error for the first row that does not
+    /// fit the type.
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<Vec<T>, Error> {
+        self.iter().map(|row| row.deserialize()).collect()
+    }
+}
+
+/// Represents a row of the result of a query.
+#[derive(Debug, Clone, Copy)]
+pub struct ResultRow<'a> {
+    columns: &'a [ResultColumn],
+    fields: &'a [String],
+    values: &'a [Const],
+}
+
+impl<'a> ResultRow<'a> {
+    /// Gets the values of the row, in the order of the columns of the result.
+    pub fn values(&self) -> &'a [Const] {
+        self.values
+    }
+
+    /// Reads the value of a column.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The position of the column in the result.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the value, or an error if there is no such column or its value
+    /// has another type.
+    pub fn get<T: FromConst>(&self, index: usize) -> Result<T, Error> {
+        let value = self
+            .values
+            .get(index)
+            .ok_or_else(|| Error::Column(format!("Column {} does not exist", index)))?;
+        T::from_const(value)
+            .map_err(|e| Error::Column(format!("Column {}: {}", self.columns[index].name, e)))
+    }
+
+    /// Reads the value of a column by its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the column in the result (`movies.title`), or the name of the
+    ///   column alone (`title`) if no other selected column has it.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the value, or an error if there is no such column or its value
+    /// has another type.
+    pub fn get_by_name<T: FromConst>(&self, name: &str) -> Result<T, Error> {
+        let index = self.index(name)?;
+        self.get(index)
+    }
+
+    /// Deserializes the row into a type implementing `serde::Deserialize`, such as a struct or
+    /// a map.
+    ///
+    /// The row is read as a map from the names of its columns to their values. A column is
+    /// named by its name alone (`title`) if no other selected column has that name, and by its
+    /// name in the result (`movies.title`) otherwise, which a struct field can take with
+    /// `#[serde(rename = "movies.title")]`. A column selected more than once is read once, as
+    /// it has the same value each time. `NULL` is read as `None` by `Option` fields.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the deserialized row, or an error if it does not fit the type.
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        let fields: serde_json::Map<String, serde_json::Value> = self
+            .fields
+            .iter()
+            .zip(self.values)
+            .map(|(name, value)| (name.clone(), value.to_serde_value()))
+            .collect();
+        serde_json::from_value(serde_json::Value::Object(fields))
+            .map_err(|e| Error::Column(format!("Cannot deserialize row: {}", e)))
+    }
+
+    /// Finds the position of a column by its name, as described by `get_by_name`.
+    fn index(&self, name: &str) -> Result<usize, Error> {
+        if let Some(index) = self.columns.iter().position(|c| c.name == name) {
+            return Ok(index);
+        }
+        if let Some(index) = self.fields.iter().position(|field| field == name) {
+            return Ok(index);
+        }
+        match self.columns.iter().any(|c| c.column_name == name) {
+            true => Err(Error::Column(format!("Column {} is ambiguous", name))),
+            false => Err(Error::Column(format!("Column {} does not exist", name))),
+        }
+    }
+}
+
+/// Converts a value of a result row to a Rust type.
+pub trait FromConst: Sized {
+    /// Converts a value.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to convert.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the converted value, or a message describing the mismatch.
+    fn from_const(value: &Const) -> Result<Self, String>;
+}
+
+impl FromConst for i64 {
+    fn from_const(value: &Const) -> Result<Self, String> {
+        match value {
+            Const::Number(n) => Ok(*n),
+            _ => Err(format!("Expected an integer but got {}", value)),
+        }
+    }
+}
+
+impl FromConst for String {
+    fn from_const(value: &Const) -> Result<Self, String> {
+        match value {
+            Const::String(s) => Ok(s.clone()),
+            _ => Err(format!("Expected a string but got {}", value)),
+        }
+    }
+}
+
+impl FromConst for Const {
+    fn from_const(value: &Const) -> Result<Self, String> {
+        Ok(value.clone())
+    }
+}
+
+impl<T: FromConst> FromConst for Option<T> {
+    fn from_const(value: &Const) -> Result<Self, String> {
+        match value {
+            Const::Null => Ok(None),
+            _ => T::from_const(value).map(Some),
+        }
+    }
+}
+
+/// Names the columns of a result for deserializing its rows: a column is named by its name
+/// alone (`title`) if no other column of the result has that name, and by its name in the
+/// result (`movies.title`) otherwise. A column selected more than once is not another column,
+/// so each of its copies has the same name.
+///
+/// # Arguments
+///
+/// * `columns` - The columns of the result.
+///
+/// # Returns
+///
+/// The name of each column, in the order of `columns`.
+fn field_names(columns: &[ResultColumn]) -> Vec<String> {
+    let mut names: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for column in columns {
+        names
+            .entry(&column.column_name)
+            .or_default()
+            .insert(&column.name);
+    }
+    columns
+        .iter()
+        .map(|column| match names[column.column_name.as_str()].len() {
+            1 => column.column_name.clone(),
+            _ => column.name.clone(),
+        })
+        .collect()
+}
+
+/// Gets the type declared for a column by the schema of its table or table provider.
+fn declared_type(database: &Database, table_name: &str, column_name: &str) -> Option<DataType> {
+    let schema = match database.table(table_name) {
+        Some(table) if table.key_column() == column_name => return Some(DataType::Integer),
+        Some(table) => table.schema.clone()?,
+        None => database.provider(table_name)?.schema(),
+    };
+    if schema.key_column() == column_name {
+        return Some(DataType::Integer);
+    }
+    schema
+        .columns
+        .iter()
+        .find(|c| c.name == column_name)
+        .map(|c| c.data_type)
+}
+
+/// Gets the type shared by the values of a column in a result, ignoring `NULL`s.
+fn result_type<'a>(values: impl Iterator<Item = &'a Const>) -> Option<DataType> {
+    let mut data_type = None;
+    for value in values {
+        let value_type = match value {
+            Const::Number(_) => DataType::Integer,
+            Const::String(_) => DataType::Text,
+            Const::Null => continue,
+        };
+        if data_type.is_some_and(|t| t != value_type) {
+            return None;
+        }
+        data_type = Some(value_type);
+    }
+    data_type
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::builder::{col, param, QueryBuilder};
+
+    #[test]
+    fn test_query() {
+        let mut connection = Connection::open("database/test_data.json").unwrap();
+        let statement = connection
+            .prepare("SELECT movies.title, movies.id FROM movies WHERE movies.id < 3")
+            .unwrap();
+        assert!(statement.is_query());
+
+        let rows = statement.query(&mut connection).unwrap();
+        assert_eq!(rows.len(), 2);
+        let columns: Vec<(&str, Option<DataType>)> = rows
+            .columns()
+            .iter()
+            .map(|c| (c.name.as_str(), c.data_type))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                ("movies.title", Some(DataType::Text)),
+                ("movies.id", Some(DataType::Integer))
+            ]
+        );
+
+        let row = rows.get(1).unwrap();
+        assert_eq!(row.get::<i64>(1).unwrap(), 2);
+        assert_eq!(row.get_by_name::<String>("title").unwrap(), "Titanic");
+        assert_eq!(
+            row.get_by_name::<Option<String>>("movies.title").unwrap(),
+            Some("Titanic".to_string())
+        );
+        assert!(row.get::<i64>(0).is_err());
+        assert!(row.get::<i64>(2).is_err());
+        assert!(row.get_by_name::<i64>("cert").is_err());
+        let titles: Vec<String> = rows.iter().map(|row| row.get(0).unwrap()).collect();
+        assert_eq!(titles, ["The Matrix", "Titanic"]);
+
+        // The same statement can run again, and statements other than queries have no rows.
+        assert_eq!(statement.query(&mut connection).unwrap().len(), 2);
+        let statement = connection
+            .prepare("CREATE INDEX idx ON movies(cert)")
+            .unwrap();
+        assert!(!statement.is_query());
+        assert!(matches!(
+            statement.query(&mut connection),
+            Err(Error::Execution(_))
+        ));
+
+        assert!(matches!(
+            connection.query("SELECT movies.title TO movies"),
+            Err(Error::Syntax(_))
+        ));
+        assert!(matches!(
+            connection.query("SELECT movies.title FROM nowhere"),
+            Err(Error::Execution(_))
+        ));
+        assert!(matches!(
+            Connection::open("database/missing.json"),
+            Err(Error::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_deserialize() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Movie {
+            title: String,
+            cert: Option<i64>,
+        }
+
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Casting {
+            #[serde(rename = "movies.id")]
+            movie_id: i64,
+            #[serde(rename = "actors_in_movies.id")]
+            casting_id: i64,
+            #[serde(rename = "actorID")]
+            actor_id: i64,
+        }
+
+        let mut connection = Connection::open("database/test_data.json").unwrap();
+        let movies: Vec<Movie> = connection
+            .query("SELECT movies.title, movies.cert FROM movies WHERE movies.id < 3")
+            .unwrap()
+            .deserialize()
+            .unwrap();
+        assert_eq!(
+            movies,
+            [
+                Movie {
+                    title: "The Matrix".to_string(),
+                    cert: Some(15)
+                },
+                Movie {
+                    title: "Titanic".to_string(),
+                    cert: Some(12)
+                }
+            ]
+        );
+
+        let rows = connection
+            .query(
+                "SELECT movies.id, actors_in_movies.id, actors_in_movies.actorID FROM movies \
+                 JOIN actors_in_movies ON actors_in_movies.movieID = movies.id \
+                 WHERE movies.id = 1",
+            )
+            .unwrap();
+        let castings: Vec<Casting> = rows.deserialize().unwrap();
+        assert_eq!(castings.len(), 2);
+        assert!(castings.iter().all(|casting| casting.movie_id == 1));
+        assert_eq!(castings[0].actor_id, 1);
+
+        let row = rows.get(0).unwrap();
+        let map: BTreeMap<String, i64> = row.deserialize().unwrap();
+        assert_eq!(
+            map.keys().collect::<Vec<_>>(),
+            ["actorID", "actors_in_movies.id", "movies.id"]
+        );
+        assert!(matches!(row.deserialize::<Movie>(), Err(Error::Column(_))));
+
+        // A column selected twice is read once, by its name alone.
+        let rows = connection
+            .query("SELECT movies.title, movies.cert, movies.title FROM movies WHERE movies.id = 2")
+            .unwrap();
+        let row = rows.get(0).unwrap();
+        let map: BTreeMap<String, serde_json::Value> = row.deserialize().unwrap();
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["cert", "title"]);
+        assert_eq!(
+            row.deserialize::<Movie>().unwrap(),
+            Movie {
+                title: "Titanic".to_string(),
+                cert: Some(12)
+            }
+        );
+        assert_eq!(row.get_by_name::<String>("title").unwrap(), "Titanic");
+    }
+
+    #[test]
+    fn test_bind() {
+        let mut connection = Connection::new();
+        connection
+            .execute("CREATE TABLE movies (title TEXT NOT NULL, year INTEGER)")
+            .unwrap();
+
+        let insert = connection
+            .prepare("INSERT INTO movies (title, year) VALUES (?, ?)")
+            .unwrap();
+        assert_eq!(insert.parameter_count(), 2);
+        for (title, year) in [("Schindler's List", Some(1993)), ("Up'); DROP", None)] {
+            insert
+                .bind(&[title.into(), year.into()])
+                .unwrap()
+                .execute(&mut connection)
+                .unwrap();
+        }
+
+        let select = connection
+            .prepare("SELECT movies.title FROM movies WHERE movies.title = $1")
+            .unwrap();
+        for title in ["Schindler's List", "Up'); DROP"] {
+            let rows = select
+                .bind(&[title.into()])
+                .unwrap()
+                .query(&mut connection)
+                .unwrap();
+            assert_eq!(rows.get(0).unwrap().get::<String>(0).unwrap(), title);
+        }
+
+        assert!(matches!(select.bind(&[]), Err(Error::Parameter(_))));
+        assert!(matches!(
+            connection.prepare("INSERT INTO movies (title, year) VALUES ($1, $3)"),
+            Err(Error::Parameter(_))
+        ));
+        assert!(matches!(
+            select.query(&mut connection),
+            Err(Error::Execution(_))
+        ));
+        let update = connection
+            .prepare("UPDATE movies SET year = $2 WHERE movies.year = $1")
+            .unwrap()
+            .bind(&[1993.into(), 1994.into()])
+            .unwrap();
+        assert!(matches!(
+            update.execute(&mut connection),
+            Ok(Outcome::Affected(1))
+        ));
+    }
+
+    #[test]
+    fn test_query_builder() {
+        let mut connection = Connection::open("database/test_data.json").unwrap();
+        let query = QueryBuilder::select(["movies.title"])
+            .from("movies")
+            .filter(col("movies.cert").le(param(1)))
+            .order_by("movies.title")
+            .build()
+            .unwrap();
+
+        let statement = PreparedStatement::from(query);
+        assert_eq!(statement.parameter_count(), 1);
+        let rows = statement
+            .bind(&[15.into()])
+            .unwrap()
+            .query(&mut connection)
+            .unwrap();
+        let titles: Vec<String> = rows.iter().map(|row| row.get(0).unwrap()).collect();
+        assert_eq!(titles, ["The Matrix", "Titanic"]);
+    }
+}
@@ -0,0 +1,575 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::database::{Diagnostic, LoadMode, Table, DEFAULT_KEY_COLUMN};
+use crate::engine::View;
+use crate::parser::{self, Const};
+
+/// Represents a record of a CSV file and the line it starts on.
+///
+/// A field is `None` if it is empty and unquoted, which is read as `NULL`, so that a quoted
+/// empty field (`""`) can hold an empty string.
+type Record = (usize, Vec<Option<String>>);
+
+/// Returns the delimiter of a delimited text file from its extension: a tab for `.tsv`
+/// files and a comma otherwise.
+///
+/// # Arguments
+///
+/// * `file_path` - The path to the file.
+pub fn delimiter_for(file_path: &str) -> char {
+    match Path::new(file_path).extension() {
+        Some(extension) if extension.eq_ignore_ascii_case("tsv") => '\t',
+        _ => ',',
+    }
+}
+
+/// Loads a table from a CSV or TSV file with a header row.
+///
+/// Fields are quoted and escaped as described by RFC 4180. A column whose values are all
+/// integers holds numbers and any other column holds strings. A value with a leading `+` or
+/// zero, such as `007`, is not read as an integer, as it would be written back without it.
+/// An empty, unquoted field is `NULL`. The values of an `id` column are the IDs of the rows,
+/// and rows without one, or every row if there is no `id` column, are given the next free ID.
+///
+/// # Arguments
+///
+/// * `file_path` - The path to the file, whose extension selects the delimiter.
+///
+/// # Returns
+///
+/// A `Result` containing the loaded table, or an I/O error. A malformed file or row is
+/// reported as an error of kind `InvalidData`.
+pub fn load_table(file_path: &str) -> Result<Table, io::Error> {
+    let (table, diagnostics) = load_table_with_mode(file_path, "", LoadMode::Lenient)?;
+    match diagnostics.into_iter().next() {
+        Some(diagnostic) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            diagnostic.reason,
+        )),
+        None => Ok(table),
+    }
+}
+
+/// Loads a table from a CSV or TSV file with a header row, as `load_table` does, handling
+/// malformed rows according to a mode.
+///
+/// A row is malformed if it has another number of fields than the header, or an ID that is
+/// not an integer or is already taken. A file that cannot be split into records, or whose
+/// header is invalid, fails the load in either mode.
+///
+/// # Arguments
+///
+/// * `file_path` - The path to the file, whose extension selects the delimiter.
+/// * `table_name` - The name of the table, used in diagnostics.
+/// * `mode` - How malformed rows are handled.
+///
+/// # Returns
+///
+/// A `Result` containing the loaded table and the diagnostics of the rows a lenient load
+/// skipped, or an I/O error. The row index of a diagnostic is the index of the row after the
+/// header. In strict mode, a malformed row is reported as an error of kind `InvalidData`.
+pub fn load_table_with_mode(
+    file_path: &str,
+    table_name: &str,
+    mode: LoadMode,
+) -> Result<(Table, Vec<Diagnostic>), io::Error> {
+    let data = fs::read_to_string(file_path)?;
+    let (table, errors) = parse_records(&data, delimiter_for(file_path))
+        .and_then(table_from_records)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let mut diagnostics = vec![];
+    for (row, reason) in errors {
+        let diagnostic = Diagnostic {
+            table_name: table_name.to_string(),
+            row: Some(row),
+            reason,
+        };
+        mode.report(&mut diagnostics, diagnostic)?;
+    }
+    Ok((table, diagnostics))
+}
+
+/// Writes the rows of a table as CSV, with a header row.
+///
+/// The primary-key column comes first, followed by the declared columns of the table or,
+/// if it has no schema, every column its rows hold in name order.
+///
+/// # Arguments
+///
+/// * `table` - The table to write.
+/// * `writer` - The writer the CSV is written to.
+/// * `delimiter` - The character separating fields.
+///
+/// # Returns
+///
+/// A `Result` indicating success or an I/O error.
+pub fn write_table<W: Write>(table: &Table, writer: &mut W, delimiter: char) -> io::Result<()> {
+    let columns: Vec<String> = match &table.schema {
+        Some(schema) => schema.columns.iter().map(|c| c.name.clone()).collect(),
+        None => table
+            .rows
+            .iter()
+            .flat_map(|row| row.columns.keys().cloned())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+    };
+
+    let mut header = vec![Some(table.key_column().to_string())];
+    header.extend(columns.iter().cloned().map(Some));
+    write_record(writer, &header, delimiter)?;
+
+    for row in &table.rows {
+        let mut fields = vec![Some(row.id.to_string())];
+        fields.extend(columns.iter().map(|column| {
+            match row.columns.get(column).unwrap_or(&Value::Null) {
+                Value::Null => None,
+                Value::String(s) => Some(s.clone()),
+                value => Some(value.to_string()),
+            }
+        }));
+        write_record(writer, &fields, delimiter)?;
+    }
+    Ok(())
+}
+
+/// Writes the rows of a query result as CSV, with a header row of the column names in the
+/// order they are selected.
+///
+/// # Arguments
+///
+/// * `view` - The query result to write.
+/// * `writer` - The writer the CSV is written to.
+/// * `delimiter` - The character separating fields.
+///
+/// # Returns
+///
+/// A `Result` indicating success or an I/O error.
+pub fn write_view<W: Write>(view: &View, writer: &mut W, delimiter: char) -> io::Result<()> {
+    let columns = view.column_names();
+
+    let header: Vec<Option<String>> = columns.iter().cloned().map(Some).collect();
+    write_record(writer, &header, delimiter)?;
+
+    for row in &view.rows {
+        let fields: Vec<Option<String>> = columns
+            .iter()
+            .map(|column| match row.get(column) {
+                None | Some(parser::Value::Const(Const::Null)) => None,
+                Some(value) => Some(value.to_string()),
+            })
+            .collect();
+        write_record(writer, &fields, delimiter)?;
+    }
+    Ok(())
+}
+
+/// Writes one record, quoting the fields that need it, followed by a CRLF line break.
+///
+/// # Arguments
+///
+/// * `writer` - The writer the record is written to.
+/// * `fields` - The fields of the record, where `None` is written as an empty field.
+/// * `delimiter` - The character separating fields.
+///
+/// # Returns
+///
+/// A `Result` indicating success or an I/O error.
+fn write_record<W: Write>(
+    writer: &mut W,
+    fields: &[Option<String>],
+    delimiter: char,
+) -> io::Result<()> {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            write!(writer, "{}", delimiter)?;
+        }
+        match field {
+            None => {}
+            Some(field) if field.is_empty() || field.contains([delimiter, '"', '\r', '\n']) => {
+                write!(writer, "\"{}\"", field.replace('"', "\"\""))?;
+            }
+            Some(field) => write!(writer, "{}", field)?,
+        }
+    }
+    write!(writer, "\r\n")
+}
+
+/// Splits delimited text into records, as described by RFC 4180.
+///
+/// Lines may end with CRLF or LF, and empty lines are skipped.
+///
+/// # Arguments
+///
+/// * `data` - The delimited text.
+/// * `delimiter` - The character separating fields.
+///
+/// # Returns
+///
+/// A `Result` containing the records, or a message describing where the text is malformed.
+fn parse_records(data: &str, delimiter: char) -> Result<Vec<Record>, String> {
+    let data = data.strip_prefix('\u{feff}').unwrap_or(data);
+
+    let mut records = vec![];
+    let mut fields = vec![];
+    let mut field = String::new();
+    // Whether the current field started with a quote, and whether it is still open.
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+
+    let mut chars = data.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() && !quoted => {
+                quoted = true;
+                in_quotes = true;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                fields.push(finish_field(&mut field, &mut quoted));
+                let record = std::mem::take(&mut fields);
+                if record != [None] {
+                    records.push((record_line, record));
+                }
+                line += 1;
+                record_line = line;
+            }
+            c if c == delimiter => fields.push(finish_field(&mut field, &mut quoted)),
+            '"' => return Err(format!("line {}: unexpected quote in a field", line)),
+            _ if quoted => {
+                return Err(format!(
+                    "line {}: unexpected character after a quoted field",
+                    line
+                ));
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(format!("line {}: unterminated quoted field", record_line));
+    }
+    if !fields.is_empty() || !field.is_empty() || quoted {
+        fields.push(finish_field(&mut field, &mut quoted));
+        records.push((record_line, fields));
+    }
+    Ok(records)
+}
+
+/// Ends the current field, resetting the parser state for the next one.
+///
+/// # Returns
+///
+/// The field, or `None` if it was empty and unquoted.
+fn finish_field(field: &mut String, quoted: &mut bool) -> Option<String> {
+    let field = std::mem::take(field);
+    let was_quoted = std::mem::take(quoted);
+    (was_quoted || !field.is_empty()).then_some(field)
+}
+
+/// Builds a table from records, the first of which names the columns, skipping malformed rows.
+///
+/// # Arguments
+///
+/// * `records` - The records of the file.
+///
+/// # Returns
+///
+/// A `Result` containing the table and the rows that were skipped, by their index after the
+/// header, with the reason. A message describing why the header is invalid otherwise.
+fn table_from_records(records: Vec<Record>) -> Result<(Table, Vec<(usize, String)>), String> {
+    let mut records = records.into_iter();
+    let (_, header) = records.next().ok_or("The file has no header row")?;
+
+    let mut columns = vec![];
+    for name in header {
+        let name = name
+            .filter(|name| !name.is_empty())
+            .ok_or("Empty column name")?;
+        if columns.contains(&name) {
+            return Err(format!("Column {} appears twice", name));
+        }
+        columns.push(name);
+    }
+
+    let mut errors = vec![];
+    let records: Vec<(usize, Record)> = records
+        .enumerate()
+        .filter(|(index, (line, fields))| {
+            let matches = fields.len() == columns.len();
+            if !matches {
+                errors.push((
+                    *index,
+                    format!(
+                        "line {}: expected {} fields but found {}",
+                        line,
+                        columns.len(),
+                        fields.len()
+                    ),
+                ));
+            }
+            matches
+        })
+        .collect();
+
+    // A column is an integer column if every value it has is an integer written as it would
+    // be written back, so codes such as 007 keep their leading zeros.
+    let integers: Vec<bool> = (0..columns.len())
+        .map(|i| {
+            let mut values = records
+                .iter()
+                .filter_map(|(_, (_, fields))| fields[i].as_ref());
+            values.clone().next().is_some()
+                && values.all(|value| {
+                    value
+                        .parse::<i64>()
+                        .is_ok_and(|number| number.to_string() == *value)
+                })
+        })
+        .collect();
+    let key = columns.iter().position(|name| name == DEFAULT_KEY_COLUMN);
+
+    let mut table = Table::new();
+    let mut unkeyed = vec![];
+    'records: for (index, (line, fields)) in records {
+        let mut id = None;
+        let mut row = BTreeMap::new();
+        for (i, field) in fields.into_iter().enumerate() {
+            if Some(i) == key {
+                if let Some(field) = field {
+                    match field.parse::<i64>() {
+                        Ok(value) => id = Some(value),
+                        Err(_) => {
+                            let reason = format!("line {}: id {} is not an integer", line, field);
+                            errors.push((index, reason));
+                            continue 'records;
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let value = match field {
+                None => Value::Null,
+                Some(field) if integers[i] => Value::from(field.parse::<i64>().unwrap()),
+                Some(field) => Value::from(field),
+            };
+            row.insert(columns[i].clone(), value);
+        }
+
+        match id {
+            Some(id) => {
+                if let Err(e) = table.add_row(id, row) {
+                    errors.push((index, format!("line {}: {}", line, e)));
+                }
+            }
+            None => unkeyed.push((index, line, row)),
+        }
+    }
+
+    for (index, line, row) in unkeyed {
+        match table.next_id() {
+            Some(id) => table.add_row(id, row).unwrap(),
+            None => errors.push((index, format!("line {}: no id is left to assign", line))),
+        }
+    }
+    errors.sort();
+    Ok((table, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temporary_path;
+
+    /// Tests splitting quoted and escaped fields into records.
+    #[test]
+    fn test_parse_records() {
+        let records = parse_records(
+            "a,b,c\r\n1,\"x, \"\"y\"\"\",\n\n2,\"multi\nline\",\"\"",
+            ',',
+        )
+        .unwrap();
+        assert_eq!(
+            records,
+            vec![
+                (
+                    1,
+                    vec![
+                        Some("a".to_string()),
+                        Some("b".to_string()),
+                        Some("c".to_string())
+                    ]
+                ),
+                (
+                    2,
+                    vec![Some("1".to_string()), Some("x, \"y\"".to_string()), None]
+                ),
+                (
+                    4,
+                    vec![
+                        Some("2".to_string()),
+                        Some("multi\nline".to_string()),
+                        Some(String::new())
+                    ]
+                ),
+            ]
+        );
+
+        assert_eq!(parse_records("a\tb", '\t').unwrap()[0].1.len(), 2);
+        assert!(parse_records("a,\"b", ',').is_err());
+        assert!(parse_records("a,b\"c", ',').is_err());
+        assert!(parse_records("\"a\"b", ',').is_err());
+    }
+
+    /// Tests loading a table with type inference and writing it back.
+    #[test]
+    fn test_load_and_write_table() {
+        let path = temporary_path("albums.tsv");
+        fs::write(
+            &path,
+            "title\tyear\tid\tcode\nAbbey Road\t1969\t3\t007\nThriller\t\t\tX1\n\"Tab\tTitle\"\t1999\t1\t\"\"\n",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+        assert_eq!(delimiter_for(path), '\t');
+
+        let table = load_table(path).unwrap();
+        fs::remove_file(path).unwrap();
+        let ids: Vec<i64> = table.rows.iter().map(|row| row.id).collect();
+        assert_eq!(ids, vec![1, 3, 4]);
+        let thriller = &table.get(4).unwrap().columns;
+        assert_eq!(thriller["title"], "Thriller");
+        assert!(thriller["year"].is_null());
+        assert_eq!(table.get(3).unwrap().columns["year"], 1969);
+        assert_eq!(table.get(3).unwrap().columns["code"], "007");
+
+        let mut output = vec![];
+        write_table(&table, &mut output, ',').unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "id,code,title,year\r\n1,\"\",Tab\tTitle,1999\r\n3,007,Abbey Road,1969\r\n4,X1,Thriller,\r\n"
+        );
+
+        // Values with a leading zero or + are read and written back as text.
+        let data = "id,code,sign,count\n1,007,+1,0\n2,008,-0,-3\n";
+        let (table, _) = table_from_records(parse_records(data, ',').unwrap()).unwrap();
+        assert_eq!(table.get(1).unwrap().columns["code"], "007");
+        assert_eq!(table.get(2).unwrap().columns["sign"], "-0");
+        assert_eq!(table.get(2).unwrap().columns["count"], -3);
+        let mut output = vec![];
+        write_table(&table, &mut output, ',').unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "id,code,count,sign\r\n1,007,0,+1\r\n2,008,-3,-0\r\n"
+        );
+
+        for data in ["", "a,a\n1,2", ",b\n1,2"] {
+            assert!(
+                table_from_records(parse_records(data, ',').unwrap()).is_err(),
+                "{}",
+                data
+            );
+        }
+        for data in ["a,b\n1", "id\nx", "id\n1\n1"] {
+            let (_, errors) = table_from_records(parse_records(data, ',').unwrap()).unwrap();
+            assert_eq!(errors.len(), 1, "{}", data);
+        }
+    }
+
+    /// Tests that malformed rows are skipped and reported, or fail a strict load.
+    #[test]
+    fn test_load_malformed_rows() {
+        let path = temporary_path("malformed.csv");
+        fs::write(
+            &path,
+            "id,name
+1,Ann
+2
+x,Bob
+1,Cid
+,Dee
+",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+
+        let (table, diagnostics) = load_table_with_mode(path, "people", LoadMode::Lenient).unwrap();
+        let names: Vec<&Value> = table.rows.iter().map(|row| &row.columns["name"]).collect();
+        assert_eq!(names, ["Ann", "Dee"]);
+        let diagnostics: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            diagnostics,
+            [
+                "people[1]: line 3: expected 2 fields but found 1",
+                "people[2]: line 4: id x is not an integer",
+                "people[3]: line 5: Row 1 already exists",
+            ]
+        );
+
+        let error = load_table(path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "line 3: expected 2 fields but found 1");
+        let error = load_table_with_mode(path, "people", LoadMode::Strict).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "people[1]: line 3: expected 2 fields but found 1"
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    /// Tests writing a query result.
+    #[test]
+    fn test_write_view() {
+        let view = View {
+            columns: parser::parse_query("SELECT movies.title, movies.cert FROM movies")
+                .unwrap()
+                .select,
+            rows: vec![
+                BTreeMap::from([
+                    (
+                        "movies.title".to_string(),
+                        parser::Value::Const(Const::String("Up, \"Pixar\"".to_string())),
+                    ),
+                    (
+                        "movies.cert".to_string(),
+                        parser::Value::Const(Const::Number(12)),
+                    ),
+                ]),
+                BTreeMap::from([("movies.cert".to_string(), parser::Value::Const(Const::Null))]),
+            ],
+        };
+
+        let mut output = vec![];
+        write_view(&view, &mut output, ',').unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "movies.title,movies.cert\r\n\"Up, \"\"Pixar\"\"\",12\r\n,\r\n"
+        );
+    }
+}
@@ -1,67 +1,1227 @@
-use serde_json::Value;
+use serde::{Deserialize, Serialize};
+use serde_json::ser::PrettyFormatter;
+use serde_json::{Serializer, Value};
+use std::borrow::Borrow;
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::io;
+use std::ops::RangeBounds;
+use std::sync::OnceLock;
+
+use crate::provider::TableProvider;
+
+/// The key of the JSON database file under which table schemas are stored.
+///
+/// It cannot clash with a table name, which only contains letters, numbers and `_`.
+const SCHEMA_KEY: &str = "$schema";
+
+/// The name that qualifies the tables of a database itself, rather than of an attached one.
+pub const MAIN_DATABASE: &str = "main";
+
+/// The key of the JSON database file under which index definitions are stored.
+const INDEXES_KEY: &str = "$indexes";
+
+/// Represents how an index is stored in the JSON database file.
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexDef {
+    table: String,
+    column: String,
+}
+
+/// The name of the primary-key column of a table that does not declare one.
+pub const DEFAULT_KEY_COLUMN: &str = "id";
 
 /// Represents a row in a database table.
-#[derive(Debug, PartialEq, Eq, Clone)]
+///
+/// The `id` is the value of the table's primary-key column, which is not part of `columns`.
+/// Rows are equal and ordered by `id` alone, as a table holds at most one row per ID.
+#[derive(Debug, Clone)]
 pub struct Row {
-    pub id: u128,
+    pub id: i64,
     pub columns: BTreeMap<String, Value>,
 }
 
-impl Ord for Row {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.id.cmp(&other.id)
+impl PartialEq for Row {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Row {}
+
+impl Ord for Row {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl PartialOrd for Row {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Row {
+    /// Converts the row to the JSON object read by `load_database`.
+    ///
+    /// # Arguments
+    ///
+    /// * `key_column` - The name of the primary-key column of the row's table.
+    pub fn to_json(&self, key_column: &str) -> Value {
+        let mut object: serde_json::Map<String, Value> = self.columns.clone().into_iter().collect();
+        object.insert(key_column.to_string(), Value::from(self.id));
+        Value::Object(object)
+    }
+}
+
+/// Rows are ordered by ID, so a table's rows can be looked up by ID directly.
+impl Borrow<i64> for Row {
+    fn borrow(&self) -> &i64 {
+        &self.id
+    }
+}
+
+/// Represents a column value that can be stored in an index.
+///
+/// Numbers sort before strings, so every key of one kind forms a contiguous range.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Key {
+    Number(i64),
+    String(String),
+}
+
+impl Key {
+    /// Converts a column value to an index key.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The column value.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the key, or `None` if the value cannot be indexed.
+    pub fn from_value(value: &Value) -> Option<Key> {
+        match value {
+            Value::Number(n) => n.as_i64().map(Key::Number),
+            Value::String(s) => Some(Key::String(s.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// Represents a secondary index, which maps the values of one column to the IDs of the rows holding them.
+#[derive(Debug, Clone)]
+pub struct Index {
+    pub column: String,
+    pub entries: BTreeMap<Key, BTreeSet<i64>>,
+}
+
+impl Index {
+    /// Creates a new, empty index over a column.
+    ///
+    /// # Arguments
+    ///
+    /// * `column` - The name of the indexed column.
+    pub fn new(column: String) -> Self {
+        Index {
+            column,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a row to the index, if it has an indexable value in the indexed column.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The row to add.
+    fn insert(&mut self, row: &Row) {
+        if let Some(key) = row.columns.get(&self.column).and_then(Key::from_value) {
+            self.entries.entry(key).or_default().insert(row.id);
+        }
+    }
+
+    /// Removes a row from the index.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The row to remove.
+    fn remove(&mut self, row: &Row) {
+        if let Some(key) = row.columns.get(&self.column).and_then(Key::from_value) {
+            if let Some(ids) = self.entries.get_mut(&key) {
+                ids.remove(&row.id);
+                if ids.is_empty() {
+                    self.entries.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+/// Represents the type of the values of a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DataType {
+    Integer,
+    Text,
+}
+
+impl DataType {
+    /// Checks whether a non-null value has this type.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to check.
+    pub fn accepts(&self, value: &Value) -> bool {
+        match self {
+            DataType::Integer => value.is_i64(),
+            DataType::Text => value.is_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for DataType {
+    /// Formats the type as it is written in SQL.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DataType::Integer => write!(f, "INTEGER"),
+            DataType::Text => write!(f, "TEXT"),
+        }
+    }
+}
+
+/// Represents the definition of a column in a table schema.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnDef {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub data_type: DataType,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub not_null: bool,
+    /// The value given to the column when a row does not provide one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+}
+
+/// Represents the columns that the rows of a table may have.
+///
+/// The primary-key column of a row is not part of its columns and needs no definition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Schema {
+    pub columns: Vec<ColumnDef>,
+    /// The name of the primary-key column, if it is not `id`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary_key: Option<String>,
+    /// Whether rows may also hold columns the schema does not define, as the rows of a table
+    /// without a schema may. A column added to such a table gives it an open schema, so the
+    /// definition of the column is kept and enforced.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub open: bool,
+}
+
+impl Schema {
+    /// Gets the name of the primary-key column, whose values are the IDs of the rows.
+    pub fn key_column(&self) -> &str {
+        self.primary_key.as_deref().unwrap_or(DEFAULT_KEY_COLUMN)
+    }
+
+    /// Checks that the columns of a row match the schema, filling in default values.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - A map of column names to their values.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or a message describing the first mismatch.
+    pub fn apply(&self, columns: &mut BTreeMap<String, Value>) -> Result<(), String> {
+        if let Some(name) = columns
+            .keys()
+            .find(|name| !self.open && self.columns.iter().all(|c| &c.name != *name))
+        {
+            return Err(format!("Column {} is not defined", name));
+        }
+
+        for column in &self.columns {
+            if !columns.contains_key(&column.name) {
+                if let Some(default) = &column.default {
+                    columns.insert(column.name.clone(), default.clone());
+                }
+            }
+
+            match columns.get(&column.name) {
+                None | Some(Value::Null) if column.not_null => {
+                    return Err(format!("Column {} cannot be null", column.name));
+                }
+                Some(value) if !value.is_null() && !column.data_type.accepts(value) => {
+                    return Err(format!(
+                        "Column {} expects {} but got {}",
+                        column.name, column.data_type, value
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Represents a problem found while inferring the schema of a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaWarning {
+    pub table_name: String,
+    pub column_name: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for SchemaWarning {
+    /// Formats the warning for display to a user.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}.{}: {}",
+            self.table_name, self.column_name, self.message
+        )
+    }
+}
+
+/// Counts the kinds of values found in a column while inferring its type.
+#[derive(Default)]
+struct ValueCounts {
+    integer: usize,
+    text: usize,
+    null: usize,
+    /// Values the engine cannot compare, by the name of their JSON type.
+    unsupported: BTreeMap<&'static str, usize>,
+}
+
+impl ValueCounts {
+    /// Counts a value.
+    fn add(&mut self, value: &Value) {
+        let unsupported = match value {
+            Value::Number(n) if n.is_i64() => {
+                self.integer += 1;
+                return;
+            }
+            Value::String(_) => {
+                self.text += 1;
+                return;
+            }
+            Value::Null => {
+                self.null += 1;
+                return;
+            }
+            Value::Number(_) => "REAL",
+            Value::Bool(_) => "BOOLEAN",
+            Value::Array(_) => "ARRAY",
+            Value::Object(_) => "OBJECT",
+        };
+        *self.unsupported.entry(unsupported).or_default() += 1;
+    }
+}
+
+/// Checks that a name can be written in SQL as the name of a table or a column: it is made of
+/// ASCII letters, digits and `_`.
+///
+/// # Arguments
+///
+/// * `name` - The unqualified name.
+///
+/// # Returns
+///
+/// `true` if the name is valid.
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Checks that every value of a row is one the engine can query: an integer, a string or `NULL`.
+///
+/// # Arguments
+///
+/// * `columns` - A map of column names to their values.
+///
+/// # Returns
+///
+/// A `Result` indicating success, or a message naming the first column with another value,
+/// such as a real number or a boolean.
+pub(crate) fn check_values(columns: &BTreeMap<String, Value>) -> Result<(), String> {
+    match columns
+        .iter()
+        .find(|(_, value)| !(value.is_i64() || value.is_string() || value.is_null()))
+    {
+        Some((name, value)) => Err(format!(
+            "Column {} holds {}, which is not an INTEGER, TEXT or NULL value",
+            name, value
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Represents a database table, which contains a set of rows.
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    pub rows: BTreeSet<Row>,
+    /// The secondary indexes of the table, by name. They are kept up to date by `add_row` and `remove_row`.
+    pub indexes: BTreeMap<String, Index>,
+    /// The columns rows must have, or `None` if rows may hold any columns.
+    pub schema: Option<Schema>,
+    /// The NDJSON file the table was streamed from, if any. Such a table is read-only and is
+    /// not written to the database file.
+    pub streamed_from: Option<String>,
+    /// The schema inferred from the rows and its warnings, kept until the rows change.
+    inferred: OnceLock<(Schema, Vec<SchemaWarning>)>,
+}
+
+impl Table {
+    /// Creates a new, empty table.
+    pub fn new() -> Self {
+        Table {
+            rows: BTreeSet::new(),
+            indexes: BTreeMap::new(),
+            schema: None,
+            streamed_from: None,
+            inferred: OnceLock::new(),
+        }
+    }
+
+    /// Checks that the columns of a row match the schema of the table, if it has one.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - A map of column names to their values, completed with default values.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or a message describing the first mismatch.
+    pub fn check_row(&self, columns: &mut BTreeMap<String, Value>) -> Result<(), String> {
+        match &self.schema {
+            Some(schema) => schema.apply(columns),
+            None => Ok(()),
+        }
+    }
+
+    /// Infers a schema from the rows of the table.
+    ///
+    /// A column is `NOT NULL` if every row has a non-null value for it. A column holding
+    /// values of several types takes the most common of `INTEGER` and `TEXT`, and a warning
+    /// is reported, as it is for values of types the engine does not support. A column with
+    /// neither `INTEGER` nor `TEXT` values, such as one holding only `NULL`, has no type to
+    /// infer: it is left out of the schema and reported.
+    ///
+    /// The schema is inferred when first asked for and kept until the rows of the table change.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table, used in warnings.
+    ///
+    /// # Returns
+    ///
+    /// The inferred schema and the warnings found while inferring it.
+    pub fn infer_schema(&self, table_name: &str) -> (&Schema, &[SchemaWarning]) {
+        let (schema, warnings) = self.inferred.get_or_init(|| self.infer(table_name));
+        (schema, warnings)
+    }
+
+    /// Infers a schema from the rows of the table, as described by `infer_schema`.
+    fn infer(&self, table_name: &str) -> (Schema, Vec<SchemaWarning>) {
+        let mut counts: BTreeMap<&str, ValueCounts> = BTreeMap::new();
+        for row in &self.rows {
+            for (column, value) in &row.columns {
+                counts.entry(column).or_default().add(value);
+            }
+        }
+
+        let mut columns = vec![];
+        let mut warnings = vec![];
+        for (column_name, counts) in counts {
+            let mut warn = |message: String| {
+                warnings.push(SchemaWarning {
+                    table_name: table_name.to_string(),
+                    column_name: column_name.to_string(),
+                    message,
+                })
+            };
+
+            for (type_name, count) in &counts.unsupported {
+                warn(format!(
+                    "has {} {} value(s), which cannot be queried",
+                    count, type_name
+                ));
+            }
+            let data_type = match (counts.integer, counts.text) {
+                (0, 0) => {
+                    warn("has no INTEGER or TEXT values, so its type is unknown".to_string());
+                    continue;
+                }
+                (integer, text) if integer > text => DataType::Integer,
+                _ => DataType::Text,
+            };
+            if counts.integer > 0 && counts.text > 0 {
+                warn(format!(
+                    "mixes {} INTEGER and {} TEXT values, inferred as {}",
+                    counts.integer, counts.text, data_type
+                ));
+            }
+
+            let present = counts.integer + counts.text + counts.unsupported.values().sum::<usize>();
+            columns.push(ColumnDef {
+                name: column_name.to_string(),
+                data_type,
+                not_null: present == self.rows.len(),
+                default: None,
+            });
+        }
+
+        (
+            Schema {
+                columns,
+                primary_key: None,
+                open: false,
+            },
+            warnings,
+        )
+    }
+
+    /// Gets the name of the primary-key column, whose values are the IDs of the rows.
+    pub fn key_column(&self) -> &str {
+        self.schema
+            .as_ref()
+            .map_or(DEFAULT_KEY_COLUMN, Schema::key_column)
+    }
+
+    /// Gets the ID to give a new row that does not provide one.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the ID after the greatest one in the table (`1` for an empty
+    /// table), or `None` if the greatest ID is `i64::MAX`.
+    pub fn next_id(&self) -> Option<i64> {
+        match self.rows.last() {
+            Some(row) => row.id.checked_add(1),
+            None => Some(1),
+        }
+    }
+
+    /// Adds a row to the table.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the row.
+    /// * `columns` - A map of column names to their values.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or a message if the table already has a row with that ID.
+    pub fn add_row(&mut self, id: i64, columns: BTreeMap<String, Value>) -> Result<(), String> {
+        if self.rows.contains(&id) {
+            return Err(format!("Row {} already exists", id));
+        }
+
+        self.inferred = OnceLock::new();
+        let row = Row { id, columns };
+        for index in self.indexes.values_mut() {
+            index.insert(&row);
+        }
+        self.rows.insert(row);
+        Ok(())
+    }
+
+    /// Removes a row from the table.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the row.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the removed row, or `None` if there is no row with that ID.
+    pub fn remove_row(&mut self, id: i64) -> Option<Row> {
+        let row = self.rows.take(&id)?;
+        self.inferred = OnceLock::new();
+        for index in self.indexes.values_mut() {
+            index.remove(&row);
+        }
+        Some(row)
+    }
+
+    /// Checks whether the table has a column, which is either declared in its schema or,
+    /// for a table without one or with an open one, held by one of its rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `column` - The name of the column.
+    pub fn has_column(&self, column: &str) -> bool {
+        let declared = |schema: &Schema| schema.columns.iter().any(|c| c.name == column);
+        column == self.key_column()
+            || self.schema.as_ref().is_some_and(declared)
+            || (self.schema.as_ref().is_none_or(|schema| schema.open)
+                && self.rows.iter().any(|row| row.columns.contains_key(column)))
+    }
+
+    /// Adds a column to the table, giving every row its default value.
+    ///
+    /// A table without a schema is given an open one holding the definition of the column.
+    ///
+    /// # Arguments
+    ///
+    /// * `column` - The definition of the column.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or a message describing why the column could not be added.
+    pub fn add_column(&mut self, column: ColumnDef) -> Result<(), String> {
+        if self.has_column(&column.name) {
+            return Err(format!("Column {} already exists", column.name));
+        }
+        if column.not_null && column.default.is_none() && !self.rows.is_empty() {
+            return Err(format!(
+                "Column {} cannot be NOT NULL without a default, as the table has rows",
+                column.name
+            ));
+        }
+
+        if let Some(default) = &column.default {
+            self.update_rows(|columns| {
+                columns.insert(column.name.clone(), default.clone());
+            });
+        }
+        let schema = self.schema.get_or_insert_with(|| Schema {
+            columns: vec![],
+            primary_key: None,
+            open: true,
+        });
+        schema.columns.push(column);
+        Ok(())
+    }
+
+    /// Removes a column from the table, along with its values and the indexes over it.
+    ///
+    /// # Arguments
+    ///
+    /// * `column` - The name of the column.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or a message describing why the column could not be removed.
+    pub fn drop_column(&mut self, column: &str) -> Result<(), String> {
+        if column == self.key_column() {
+            return Err(format!(
+                "Column {} is the primary key and cannot be dropped",
+                column
+            ));
+        }
+        if !self.has_column(column) {
+            return Err(format!("Column {} does not exist", column));
+        }
+
+        self.indexes.retain(|_, index| index.column != column);
+        self.update_rows(|columns| {
+            columns.remove(column);
+        });
+        if let Some(schema) = &mut self.schema {
+            schema.columns.retain(|c| c.name != column);
+            // An open schema left without definitions says no more than having no schema.
+            if schema.open && schema.columns.is_empty() && schema.primary_key.is_none() {
+                self.schema = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// Renames a column of the table, in its rows, its schema and the indexes over it.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The current name of the column.
+    /// * `to` - The new name of the column.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or a message describing why the column could not be renamed.
+    pub fn rename_column(&mut self, from: &str, to: &str) -> Result<(), String> {
+        if !self.has_column(from) {
+            return Err(format!("Column {} does not exist", from));
+        }
+        if self.has_column(to) {
+            return Err(format!("Column {} already exists", to));
+        }
+
+        if from == self.key_column() {
+            // The ID of a row is not one of its columns, so only the key column's name changes.
+            let schema = self.schema.as_mut().ok_or_else(|| {
+                format!(
+                    "Column {} of a table without a schema cannot be renamed",
+                    from
+                )
+            })?;
+            schema.primary_key = (to != DEFAULT_KEY_COLUMN).then(|| to.to_string());
+            return Ok(());
+        }
+
+        for index in self.indexes.values_mut() {
+            if index.column == from {
+                index.column = to.to_string();
+            }
+        }
+        self.update_rows(|columns| {
+            if let Some(value) = columns.remove(from) {
+                columns.insert(to.to_string(), value);
+            }
+        });
+        if let Some(schema) = &mut self.schema {
+            for c in &mut schema.columns {
+                if c.name == from {
+                    c.name = to.to_string();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Changes the columns of every row in place, then rebuilds the indexes from the new values.
+    ///
+    /// # Arguments
+    ///
+    /// * `change` - The change to make to the columns of each row.
+    fn update_rows<F: FnMut(&mut BTreeMap<String, Value>)>(&mut self, mut change: F) {
+        self.inferred = OnceLock::new();
+        self.rows = std::mem::take(&mut self.rows)
+            .into_iter()
+            .map(|mut row| {
+                change(&mut row.columns);
+                row
+            })
+            .collect();
+
+        for index in self.indexes.values_mut() {
+            index.entries.clear();
+            for row in &self.rows {
+                index.insert(row);
+            }
+        }
+    }
+
+    /// Builds an index over a column from the rows currently in the table.
+    ///
+    /// # Arguments
+    ///
+    /// * `index_name` - The name of the index.
+    /// * `column` - The name of the column to index.
+    pub fn create_index(&mut self, index_name: String, column: String) {
+        let mut index = Index::new(column);
+        for row in &self.rows {
+            index.insert(row);
+        }
+        self.indexes.insert(index_name, index);
+    }
+
+    /// Finds an index over a column.
+    ///
+    /// # Arguments
+    ///
+    /// * `column` - The name of the column.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the name of the index, or `None` if the column is not indexed.
+    pub fn index_on(&self, column: &str) -> Option<&str> {
+        self.indexes
+            .iter()
+            .find(|(_, index)| index.column == column)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Looks up rows through an index.
+    ///
+    /// # Arguments
+    ///
+    /// * `index_name` - The name of the index.
+    /// * `range` - The range of keys to look up.
+    ///
+    /// # Returns
+    ///
+    /// The rows whose indexed value falls within `range`, ordered by that value.
+    pub fn index_range<R: RangeBounds<Key>>(&self, index_name: &str, range: R) -> Vec<&Row> {
+        let index = &self.indexes[index_name];
+        index
+            .entries
+            .range(range)
+            .flat_map(|(_, ids)| ids)
+            .filter_map(|&id| self.get(id))
+            .collect()
+    }
+
+    /// Gets a row by its ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the row.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the row, or `None` if there is no row with that ID.
+    pub fn get(&self, id: i64) -> Option<&Row> {
+        self.rows.get(&id)
+    }
+
+    /// Iterates over the rows whose IDs fall within a range, in ID order.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - The range of IDs.
+    pub fn range<R: RangeBounds<i64>>(&self, range: R) -> impl Iterator<Item = &Row> {
+        self.rows.range(range)
+    }
+}
+
+/// Represents a database, which contains multiple tables.
+#[derive(Debug, Default)]
+pub struct Database {
+    pub tables: BTreeMap<String, Table>,
+    /// The path of the file the database was loaded from, which changes are written back to.
+    pub path: Option<String>,
+    /// The path of the directory the database was loaded from by `load_directory`, which
+    /// changes cannot be written back to.
+    pub directory: Option<String>,
+    /// The warnings found while inferring the schemas of the tables when loading the database.
+    pub warnings: Vec<SchemaWarning>,
+    /// The malformed tables and rows skipped when loading the database.
+    pub diagnostics: Vec<Diagnostic>,
+    /// The databases attached to this one, by name, whose tables can be queried alongside its own.
+    pub attached: BTreeMap<String, Database>,
+    /// The read-only tables whose rows are read from providers rather than stored, by name.
+    pub providers: BTreeMap<String, Box<dyn TableProvider>>,
+}
+
+impl Database {
+    /// Creates a new, empty database.
+    pub fn new() -> Self {
+        Database {
+            tables: BTreeMap::new(),
+            path: None,
+            directory: None,
+            warnings: Vec::new(),
+            diagnostics: Vec::new(),
+            attached: BTreeMap::new(),
+            providers: BTreeMap::new(),
+        }
+    }
+
+    /// Finds a table that can be queried.
+    ///
+    /// The name of the table may be qualified by the name of an attached database
+    /// (`music.albums`), or by `main` for a table of this database. An unqualified name that
+    /// is not a table of this database refers to the table of that name in the attached
+    /// databases, if exactly one of them has it.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the table, or `None` if no single table has that name.
+    pub fn table(&self, table_name: &str) -> Option<&Table> {
+        self.find(table_name, |_, database, name| database.tables.get(name))
+    }
+
+    /// Finds a table provider registered with `register_table`.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table, which may be qualified as in `Database::table`.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the provider, or `None` if no single provider has that name.
+    pub fn provider(&self, table_name: &str) -> Option<&dyn TableProvider> {
+        self.find(table_name, |_, database, name| {
+            database
+                .providers
+                .get(name)
+                .map(|provider| provider.as_ref())
+        })
+    }
+
+    /// Checks whether a table or a table provider can be queried under a name.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table, which may be qualified as in `Database::table`.
+    pub fn has_table(&self, table_name: &str) -> bool {
+        self.table(table_name).is_some() || self.provider(table_name).is_some()
+    }
+
+    /// Resolves the name of a table or a table provider to the name qualified by the database
+    /// that holds it (`main.movies`), so that the different names of one table can be compared.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table, which may be qualified as in `Database::table`.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the qualified name, or `None` if no single table has that name.
+    pub fn resolve_table(&self, table_name: &str) -> Option<String> {
+        self.find(table_name, |database_name, database, name| {
+            (database.tables.contains_key(name) || database.providers.contains_key(name))
+                .then(|| format!("{}.{}", database_name, name))
+        })
+    }
+
+    /// Finds an item of this database or of an attached one by its possibly qualified name,
+    /// following the rules of `Database::table`.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table.
+    /// * `get` - Gets the item of a database, given the name of the database, by its
+    ///   unqualified name.
+    fn find<'d, T>(
+        &'d self,
+        table_name: &str,
+        get: impl Fn(&'d str, &'d Database, &str) -> Option<T>,
+    ) -> Option<T> {
+        match table_name.split_once('.') {
+            Some((MAIN_DATABASE, name)) => get(MAIN_DATABASE, self, name),
+            Some((database_name, name)) => {
+                let (database_name, database) = self.attached.get_key_value(database_name)?;
+                get(database_name, database, name)
+            }
+            None => get(MAIN_DATABASE, self, table_name).or_else(|| {
+                let mut items = self
+                    .attached
+                    .iter()
+                    .filter_map(|(database_name, database)| {
+                        get(database_name, database, table_name)
+                    });
+                match (items.next(), items.next()) {
+                    (Some(item), None) => Some(item),
+                    _ => None,
+                }
+            }),
+        }
+    }
+
+    /// Registers a table provider as a read-only table, which queries can read and join with
+    /// the other tables of the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table.
+    /// * `provider` - The provider of the rows of the table.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or a message if the name is not one SQL can refer to, or
+    /// a table already has it.
+    pub fn register_table(
+        &mut self,
+        table_name: &str,
+        provider: impl TableProvider + 'static,
+    ) -> Result<(), String> {
+        if !is_valid_name(table_name) {
+            return Err(format!("Invalid table name {}", table_name));
+        }
+        if self.tables.contains_key(table_name) || self.providers.contains_key(table_name) {
+            return Err(format!("Table {} already exists", table_name));
+        }
+        self.providers
+            .insert(table_name.to_string(), Box::new(provider));
+        Ok(())
+    }
+
+    /// Gets a table of this database that statements can change.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the table, or a message if it does not exist or is read-only, as
+    /// a provider or a table streamed from an NDJSON file is.
+    pub fn table_mut(&mut self, table_name: &str) -> Result<&mut Table, String> {
+        if self.providers.contains_key(table_name) {
+            return Err(format!("Table {} is read-only", table_name));
+        }
+        let table = self
+            .tables
+            .get_mut(table_name)
+            .ok_or_else(|| format!("Table {} does not exist", table_name))?;
+        if let Some(file_path) = &table.streamed_from {
+            return Err(format!(
+                "Table {} is read-only, as it is streamed from {} and changes are not \
+                 written back to that file",
+                table_name, file_path
+            ));
+        }
+        Ok(table)
+    }
+
+    /// Attaches another database, so its tables can be queried alongside this one's.
+    ///
+    /// # Arguments
+    ///
+    /// * `database_name` - The name that qualifies the tables of the attached database.
+    /// * `database` - The database to attach.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or a message if the name is already taken.
+    pub fn attach(&mut self, database_name: &str, database: Database) -> Result<(), String> {
+        if database_name == MAIN_DATABASE || self.attached.contains_key(database_name) {
+            return Err(format!("Database {} is already attached", database_name));
+        }
+        self.attached.insert(database_name.to_string(), database);
+        Ok(())
+    }
+
+    /// Gets the schema of a table.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table, which may be qualified as in `Database::table`.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the schema declared for the table or, if it has none, the schema
+    /// inferred from its current rows by `Table::infer_schema`. An open schema is completed
+    /// with the inferred definitions of the columns it does not define. `None` if the table
+    /// does not exist.
+    pub fn schema(&self, table_name: &str) -> Option<Schema> {
+        let Some(table) = self.table(table_name) else {
+            return Some(self.provider(table_name)?.schema());
+        };
+        let inferred = || table.infer_schema(table_name).0;
+        match &table.schema {
+            Some(schema) if schema.open => {
+                let mut schema = schema.clone();
+                let undefined: Vec<ColumnDef> = inferred()
+                    .columns
+                    .iter()
+                    .filter(|c| schema.columns.iter().all(|defined| defined.name != c.name))
+                    .cloned()
+                    .collect();
+                schema.columns.extend(undefined);
+                Some(schema)
+            }
+            Some(schema) => Some(schema.clone()),
+            None => Some(inferred().clone()),
+        }
+    }
+
+    /// Adds a new, empty table with a schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table.
+    /// * `schema` - The schema of the table.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or a message describing why the table could not be added.
+    pub fn add_table(&mut self, table_name: &str, schema: Schema) -> Result<(), String> {
+        if self.tables.contains_key(table_name) || self.providers.contains_key(table_name) {
+            return Err(format!("Table {} already exists", table_name));
+        }
+
+        let mut table = Table::new();
+        table.schema = Some(schema);
+        self.insert_table(table_name.to_string(), table);
+        Ok(())
+    }
+
+    /// Loads a table from a newline-delimited JSON file, which holds one row object per line.
+    ///
+    /// The file is read one line at a time, so it is never held in memory as a whole. Empty
+    /// lines are skipped, and the row index of a diagnostic is the index of the row's line.
+    /// The table is read-only, as changes are not written back to the NDJSON file, so
+    /// statements that change it fail. It is not written to the database file either, as the
+    /// NDJSON file holds its rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the new table.
+    /// * `file_path` - The path to the NDJSON file.
+    /// * `mode` - How malformed rows are handled. Rows skipped by a lenient load are listed
+    ///   in `Database::diagnostics`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or an I/O error, which is also returned if a table or a
+    /// table provider already has the name.
+    pub fn load_ndjson_table(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        mode: LoadMode,
+    ) -> Result<(), io::Error> {
+        if self.tables.contains_key(table_name) || self.providers.contains_key(table_name) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("Table {} already exists", table_name),
+            ));
+        }
+
+        let reader = io::BufReader::new(fs::File::open(file_path)?);
+        let mut loader = TableLoader::new(None);
+        let mut diagnostics = vec![];
+        let mut report = |row, reason| {
+            let diagnostic = Diagnostic {
+                table_name: table_name.to_string(),
+                row: Some(row),
+                reason,
+            };
+            mode.report(&mut diagnostics, diagnostic)
+        };
+
+        for (i, line) in io::BufRead::lines(reader).enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let result = serde_json::from_str(&line)
+                .map_err(|e| format!("line is not valid JSON: {}", e))
+                .and_then(|row| loader.add(i, &row));
+            if let Err(e) = result {
+                report(i, e)?;
+            }
+        }
+
+        let (mut table, errors) = loader.finish();
+        for (i, e) in errors {
+            report(i, e)?;
+        }
+        table.streamed_from = Some(file_path.to_string());
+
+        self.diagnostics.extend(diagnostics);
+        self.warnings
+            .extend_from_slice(table.infer_schema(table_name).1);
+        self.insert_table(table_name.to_string(), table);
+        Ok(())
     }
-}
 
-impl PartialOrd for Row {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    /// Removes a table, along with its indexes.
+    ///
+    /// # Arguments
+    ///
+    /// * `table_name` - The name of the table.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the removed table, or a message if the table does not exist.
+    pub fn drop_table(&mut self, table_name: &str) -> Result<Table, String> {
+        self.table_mut(table_name)?;
+        Ok(self.tables.remove(table_name).unwrap())
     }
-}
 
-/// Represents a database table, which contains a set of rows.
-#[derive(Debug)]
-pub struct Table {
-    pub rows: BTreeSet<Row>,
-}
+    /// Converts the database to the JSON layout read by `load_database`, leaving out the
+    /// tables streamed from NDJSON files.
+    pub fn to_json(&self) -> Value {
+        let stored = || {
+            self.tables
+                .iter()
+                .filter(|(_, table)| table.streamed_from.is_none())
+        };
+        let mut tables: serde_json::Map<String, Value> = stored()
+            .map(|(table_name, table)| {
+                let rows = table
+                    .rows
+                    .iter()
+                    .map(|row| row.to_json(table.key_column()))
+                    .collect();
+                (table_name.clone(), Value::Array(rows))
+            })
+            .collect();
 
-impl Table {
-    /// Creates a new, empty table.
-    pub fn new() -> Self {
-        Table {
-            rows: BTreeSet::new(),
+        let schemas: serde_json::Map<String, Value> = stored()
+            .filter_map(|(table_name, table)| {
+                let schema = serde_json::to_value(table.schema.as_ref()?).unwrap();
+                Some((table_name.clone(), schema))
+            })
+            .collect();
+        if !schemas.is_empty() {
+            tables.insert(SCHEMA_KEY.to_string(), Value::Object(schemas));
         }
+
+        let indexes: serde_json::Map<String, Value> = stored()
+            .flat_map(|(table_name, table)| {
+                table.indexes.iter().map(|(index_name, index)| {
+                    let index = IndexDef {
+                        table: table_name.clone(),
+                        column: index.column.clone(),
+                    };
+                    (index_name.clone(), serde_json::to_value(index).unwrap())
+                })
+            })
+            .collect();
+        if !indexes.is_empty() {
+            tables.insert(INDEXES_KEY.to_string(), Value::Object(indexes));
+        }
+
+        Value::Object(tables)
     }
 
-    /// Adds a row to the table.
+    /// Writes the database back to the file it was loaded from.
     ///
-    /// # Arguments
+    /// A database that was not loaded from a file or a directory is left untouched.
     ///
-    /// * `id` - The ID of the row.
-    /// * `columns` - A map of column names to their values.
-    pub fn add_row(&mut self, id: u128, columns: BTreeMap<String, Value>) {
-        self.rows.insert(Row { id, columns });
+    /// # Returns
+    ///
+    /// A `Result` indicating success or an I/O error. An error of kind `InvalidInput` is
+    /// returned, and the file left untouched, if `check_writable` fails.
+    pub fn persist(&self) -> Result<(), io::Error> {
+        self.check_writable()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        match &self.path {
+            Some(path) => save_database(self, path),
+            None => Ok(()),
+        }
     }
-}
 
-/// Represents a database, which contains multiple tables.
-#[derive(Debug)]
-pub struct Database {
-    pub tables: BTreeMap<String, Table>,
-}
+    /// Checks that the database can be written back to the file it was loaded from without
+    /// losing data.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or a message if the database was loaded from a directory,
+    /// or if loading skipped malformed tables or rows, which the file would no longer hold.
+    /// `discard_skipped` allows writing it anyway. Rows skipped from an NDJSON file stay in
+    /// that file, so they do not count.
+    pub fn check_writable(&self) -> Result<(), String> {
+        if let Some(directory) = &self.directory {
+            return Err(format!(
+                "{} cannot be changed, as changes are not written back to a directory",
+                directory
+            ));
+        }
 
-impl Database {
-    /// Creates a new, empty database.
-    pub fn new() -> Self {
-        Database {
-            tables: BTreeMap::new(),
+        let skipped = self
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| {
+                self.tables
+                    .get(&diagnostic.table_name)
+                    .is_none_or(|table| table.streamed_from.is_none())
+            })
+            .count();
+        match &self.path {
+            Some(path) if skipped > 0 => Err(format!(
+                "{} cannot be changed, as {} malformed table(s) or row(s) were skipped when \
+                 loading it and writing it back would lose them",
+                path, skipped
+            )),
+            _ => Ok(()),
         }
     }
 
+    /// Accepts that the malformed tables and rows skipped when loading the database are lost
+    /// when it is written back, by clearing `diagnostics`.
+    pub fn discard_skipped(&mut self) {
+        self.diagnostics.clear();
+    }
+
     /// Creates a new, empty table.
     ///
     /// # Arguments
@@ -80,9 +1240,103 @@ impl Database {
     fn insert_table(&mut self, table_name: String, table: Table) {
         self.tables.insert(table_name, table);
     }
+
+    /// Creates an index over a column of a table.
+    ///
+    /// # Arguments
+    ///
+    /// * `index_name` - The name of the index, which must be unique within the database.
+    /// * `table_name` - The name of the table.
+    /// * `column` - The name of the column to index.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or a message describing why the index could not be
+    /// created, such as a column that the table's schema does not define. A table without a
+    /// schema, or with an open one, can be indexed on any column, as its rows may hold any.
+    pub fn create_index(
+        &mut self,
+        index_name: &str,
+        table_name: &str,
+        column: &str,
+    ) -> Result<(), String> {
+        if self
+            .tables
+            .values()
+            .any(|table| table.indexes.contains_key(index_name))
+        {
+            return Err(format!("Index {} already exists", index_name));
+        }
+
+        let table = self.table_mut(table_name)?;
+        if table.schema.as_ref().is_some_and(|schema| !schema.open) && !table.has_column(column) {
+            return Err(format!("Column {} does not exist", column));
+        }
+        table.create_index(index_name.to_string(), column.to_string());
+        Ok(())
+    }
+}
+
+/// Represents how malformed tables and rows are handled when loading a database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoadMode {
+    /// Malformed tables and rows are skipped, and a diagnostic is recorded for each.
+    #[default]
+    Lenient,
+    /// Loading fails at the first malformed table or row.
+    Strict,
+}
+
+impl LoadMode {
+    /// Handles a malformed table or row according to the mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `diagnostics` - The diagnostics that a lenient load records `diagnostic` in.
+    /// * `diagnostic` - The problem found.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating that loading can continue, or an error of kind `InvalidData`
+    /// for a strict load.
+    pub(crate) fn report(
+        self,
+        diagnostics: &mut Vec<Diagnostic>,
+        diagnostic: Diagnostic,
+    ) -> io::Result<()> {
+        match self {
+            LoadMode::Lenient => {
+                diagnostics.push(diagnostic);
+                Ok(())
+            }
+            LoadMode::Strict => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                diagnostic.to_string(),
+            )),
+        }
+    }
+}
+
+/// Represents a malformed table or row found while loading a database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub table_name: String,
+    /// The index of the row in the table's JSON array, or `None` if the whole table is malformed.
+    pub row: Option<usize>,
+    pub reason: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    /// Formats the diagnostic for display to a user.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.row {
+            Some(row) => write!(f, "{}[{}]: {}", self.table_name, row, self.reason),
+            None => write!(f, "{}: {}", self.table_name, self.reason),
+        }
+    }
 }
 
-/// Loads a database from a JSON file.
+/// Loads a database from a JSON file, skipping malformed tables and rows.
 ///
 /// # Arguments
 ///
@@ -90,43 +1344,363 @@ impl Database {
 ///
 /// # Returns
 ///
-/// A `Result` containing the loaded database or an I/O error.
+/// A `Result` containing the loaded database or an I/O error. The skipped tables and rows
+/// are listed in `Database::diagnostics`.
 pub fn load_database(file_path: &str) -> Result<Database, io::Error> {
+    load_database_with_mode(file_path, LoadMode::Lenient)
+}
+
+/// Loads a database from a JSON file, or from a directory as described by `load_directory`.
+///
+/// # Arguments
+///
+/// * `file_path` - The path to the JSON file or directory.
+/// * `mode` - How malformed tables and rows are handled.
+///
+/// # Returns
+///
+/// A `Result` containing the loaded database or an I/O error. In strict mode, a malformed
+/// table or row is reported as an error of kind `InvalidData`.
+pub fn load_database_with_mode(file_path: &str, mode: LoadMode) -> Result<Database, io::Error> {
+    if std::path::Path::new(file_path).is_dir() {
+        return load_directory(file_path, mode);
+    }
+
     let data = fs::read_to_string(file_path)?;
-    let data: Value = serde_json::from_str(&data).expect("Failed to parse JSON.");
+    let data: Value = serde_json::from_str(&data)?;
+    let tables = data.as_object().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "The database must be a JSON object of tables",
+        )
+    })?;
 
     let mut db = Database::new();
+    let mut diagnostics = vec![];
+    let mut report = |diagnostic: Diagnostic| mode.report(&mut diagnostics, diagnostic);
 
-    if let Some(tables) = data.as_object() {
-        for (table_name, rows) in tables {
-            let mut table = db.create_table();
-
-            if let Some(rows_array) = rows.as_array() {
-                for row in rows_array {
-                    if let Some(row_object) = row.as_object() {
-                        if let Some(id) = row_object.get("id").and_then(Value::as_u64) {
-                            let columns = row_object
-                                .iter()
-                                .filter(|&(k, _)| k != "id")
-                                .map(|(k, v)| (k.clone(), v.clone()))
-                                .collect();
-
-                            table.add_row(id as u128, columns);
-                        }
-                    }
-                }
+    let mut schemas: BTreeMap<String, Schema> = match tables.get(SCHEMA_KEY) {
+        Some(schemas) => serde_json::from_value(schemas.clone())?,
+        None => BTreeMap::new(),
+    };
+    let indexes: BTreeMap<String, IndexDef> = match tables.get(INDEXES_KEY) {
+        Some(indexes) => serde_json::from_value(indexes.clone())?,
+        None => BTreeMap::new(),
+    };
+
+    let mut loaded = vec![];
+    for (table_name, rows) in tables {
+        if table_name == SCHEMA_KEY || table_name == INDEXES_KEY {
+            continue;
+        }
+        let diagnostic = |row, reason: String| Diagnostic {
+            table_name: table_name.clone(),
+            row,
+            reason,
+        };
+
+        let Some(rows_array) = rows.as_array() else {
+            report(diagnostic(
+                None,
+                "table is not an array of rows".to_string(),
+            ))?;
+            continue;
+        };
+
+        let mut loader = TableLoader::new(schemas.remove(table_name));
+        for (i, row) in rows_array.iter().enumerate() {
+            if let Err(e) = loader.add(i, row) {
+                report(diagnostic(Some(i), e))?;
+            }
+        }
+
+        let (table, errors) = loader.finish();
+        for (i, e) in errors {
+            report(diagnostic(Some(i), e))?;
+        }
+        loaded.push((table_name.clone(), table));
+    }
+
+    for (table_name, table) in loaded {
+        if table.schema.as_ref().is_none_or(|schema| schema.open) {
+            db.warnings
+                .extend_from_slice(table.infer_schema(&table_name).1);
+        }
+        db.insert_table(table_name, table);
+    }
+
+    // Tables that were created but never given any rows.
+    for (table_name, schema) in schemas {
+        let mut table = db.create_table();
+        table.schema = Some(schema);
+        db.insert_table(table_name, table);
+    }
+
+    for (index_name, index) in indexes {
+        if let Err(e) = db.create_index(&index_name, &index.table, &index.column) {
+            report(Diagnostic {
+                table_name: index.table,
+                row: None,
+                reason: e,
+            })?;
+        }
+    }
+
+    db.diagnostics = diagnostics;
+    db.path = Some(file_path.to_string());
+    Ok(db)
+}
+
+/// Loads a database from a directory, in which each file is a table named after the file.
+///
+/// A `.json` file holds an array of row objects, a `.ndjson` file holds one row object per
+/// line, and a `.csv` or `.tsv` file is read by `csv::load_table_with_mode`. Other files are
+/// ignored. The database cannot be changed by statements, as it is not written back to the
+/// directory.
+///
+/// # Arguments
+///
+/// * `dir_path` - The path to the directory.
+/// * `mode` - How malformed tables and rows are handled. A file that cannot be read as a
+///   table, or whose name is not a valid table name, counts as a malformed table.
+///
+/// # Returns
+///
+/// A `Result` containing the loaded database or an I/O error.
+pub fn load_directory(dir_path: &str, mode: LoadMode) -> Result<Database, io::Error> {
+    let mut paths = fs::read_dir(dir_path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, io::Error>>()?;
+    paths.sort();
+
+    let mut db = Database::new();
+    for path in paths {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        let (Some(extension), Some(table_name), Some(file_path)) = (
+            extension,
+            path.file_stem().and_then(|stem| stem.to_str()),
+            path.to_str(),
+        ) else {
+            continue;
+        };
+
+        if !matches!(extension.as_str(), "json" | "ndjson" | "csv" | "tsv") {
+            continue;
+        }
+
+        let invalid = |message: String| Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        let result = if !is_valid_name(table_name) {
+            invalid(format!("{} is not a valid table name", table_name))
+        } else if db.tables.contains_key(table_name) {
+            invalid(format!("{} is a second file for the table", file_path))
+        } else {
+            match extension.as_str() {
+                "ndjson" => db.load_ndjson_table(table_name, file_path, mode),
+                "json" => load_json_table(&mut db, table_name, file_path, mode),
+                _ => crate::csv::load_table_with_mode(file_path, table_name, mode).map(
+                    |(table, diagnostics)| {
+                        db.diagnostics.extend(diagnostics);
+                        db.warnings
+                            .extend_from_slice(table.infer_schema(table_name).1);
+                        db.insert_table(table_name.to_string(), table);
+                    },
+                ),
             }
+        };
 
-            db.insert_table(table_name.clone(), table);
+        // A strict load of a table fails with the diagnostic of its first malformed row.
+        if let Err(e) = result {
+            if mode == LoadMode::Strict {
+                return Err(e);
+            }
+            db.diagnostics.push(Diagnostic {
+                table_name: table_name.to_string(),
+                row: None,
+                reason: e.to_string(),
+            });
         }
     }
 
+    db.directory = Some(dir_path.to_string());
     Ok(db)
 }
 
+/// Loads a table from a JSON file holding an array of row objects.
+///
+/// # Arguments
+///
+/// * `db` - The database the table is added to.
+/// * `table_name` - The name of the table.
+/// * `file_path` - The path to the JSON file.
+/// * `mode` - How malformed rows are handled.
+///
+/// # Returns
+///
+/// A `Result` indicating success or an I/O error.
+fn load_json_table(
+    db: &mut Database,
+    table_name: &str,
+    file_path: &str,
+    mode: LoadMode,
+) -> Result<(), io::Error> {
+    let data: Value = serde_json::from_str(&fs::read_to_string(file_path)?)?;
+    let rows = data.as_array().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "table is not an array of rows")
+    })?;
+
+    let mut loader = TableLoader::new(None);
+    let mut diagnostics = vec![];
+    let mut report = |row, reason| {
+        let diagnostic = Diagnostic {
+            table_name: table_name.to_string(),
+            row: Some(row),
+            reason,
+        };
+        mode.report(&mut diagnostics, diagnostic)
+    };
+    for (i, row) in rows.iter().enumerate() {
+        if let Err(e) = loader.add(i, row) {
+            report(i, e)?;
+        }
+    }
+    let (table, errors) = loader.finish();
+    for (i, e) in errors {
+        report(i, e)?;
+    }
+
+    db.diagnostics.extend(diagnostics);
+    db.warnings
+        .extend_from_slice(table.infer_schema(table_name).1);
+    db.insert_table(table_name.to_string(), table);
+    Ok(())
+}
+
+/// Builds a table from row objects added one at a time.
+///
+/// Rows without an ID are given one once every explicit ID is known.
+struct TableLoader {
+    table: Table,
+    key_column: String,
+    /// The rows without an ID, with their index among the rows added.
+    unkeyed: Vec<(usize, BTreeMap<String, Value>)>,
+}
+
+impl TableLoader {
+    /// Creates a loader for a table.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - The declared schema of the table, which rows are checked against.
+    fn new(schema: Option<Schema>) -> Self {
+        let mut table = Table::new();
+        table.schema = schema;
+        TableLoader {
+            key_column: table.key_column().to_string(),
+            table,
+            unkeyed: vec![],
+        }
+    }
+
+    /// Adds a row to the table.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the row among the rows of the table.
+    /// * `row` - The row, which should be a JSON object.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or the reason the row was skipped.
+    fn add(&mut self, index: usize, row: &Value) -> Result<(), String> {
+        let row_object = row.as_object().ok_or("row is not an object")?;
+
+        let mut columns = row_object
+            .iter()
+            .filter(|&(k, _)| *k != self.key_column)
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        self.table.check_row(&mut columns)?;
+
+        match row_object.get(&self.key_column) {
+            None | Some(Value::Null) => {
+                self.unkeyed.push((index, columns));
+                Ok(())
+            }
+            Some(id) => {
+                let id = id
+                    .as_i64()
+                    .ok_or_else(|| format!("{} {} is not an integer", self.key_column, id))?;
+                self.table.add_row(id, columns)
+            }
+        }
+    }
+
+    /// Gives the rows without an ID the next free IDs and returns the table.
+    ///
+    /// # Returns
+    ///
+    /// The table, and the rows that could not be given an ID with the reason.
+    fn finish(mut self) -> (Table, Vec<(usize, String)>) {
+        let mut errors = vec![];
+        for (index, columns) in self.unkeyed {
+            match self.table.next_id() {
+                Some(id) => self.table.add_row(id, columns).unwrap(),
+                None => errors.push((index, format!("no {} is left to assign", self.key_column))),
+            }
+        }
+        (self.table, errors)
+    }
+}
+
+/// Saves a database to a JSON file, in the layout read by `load_database`.
+///
+/// The database is written to a temporary file next to `file_path`, which then replaces it,
+/// so the file always holds either the old or the new database.
+///
+/// # Arguments
+///
+/// * `database` - The database to save.
+/// * `file_path` - The path to the JSON file.
+///
+/// # Returns
+///
+/// A `Result` indicating success or an I/O error.
+pub fn save_database(database: &Database, file_path: &str) -> Result<(), io::Error> {
+    let path = std::path::Path::new(file_path);
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a file path", file_path),
+        )
+    })?;
+    let mut temporary_name = std::ffi::OsString::from(".");
+    temporary_name.push(file_name);
+    temporary_name.push(format!(".{}.tmp", std::process::id()));
+    let temporary_path = path.with_file_name(temporary_name);
+
+    let mut data = Vec::new();
+    let mut serializer =
+        Serializer::with_formatter(&mut data, PrettyFormatter::with_indent(b"    "));
+    database.to_json().serialize(&mut serializer)?;
+
+    let result = fs::File::create(&temporary_path).and_then(|mut file| {
+        io::Write::write_all(&mut file, &data)?;
+        file.sync_all()?;
+        fs::rename(&temporary_path, path)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary_path);
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::temporary_path;
 
     /// Tests loading a database with a correct file name.
     #[test]
@@ -135,6 +1709,451 @@ mod tests {
         load_database(file_name).unwrap();
     }
 
+    /// Tests that an index stays up to date and looks up ranges of keys.
+    #[test]
+    fn index_range() {
+        let mut db = load_database("database/test_data.json").unwrap();
+        db.create_index("idx_cert", "movies", "cert").unwrap();
+        assert!(db.create_index("idx_cert", "actors", "name").is_err());
+        assert!(db.create_index("idx_missing", "missing", "name").is_err());
+
+        let movies = db.tables.get_mut("movies").unwrap();
+        let columns = BTreeMap::from([
+            ("title".to_string(), Value::from("Heat")),
+            ("cert".to_string(), Value::from(15)),
+        ]);
+        movies.add_row(4, columns).unwrap();
+
+        let ids: Vec<i64> = movies
+            .index_range("idx_cert", Key::Number(15)..=Key::Number(15))
+            .iter()
+            .map(|row| row.id)
+            .collect();
+        assert_eq!(ids, vec![1, 4]);
+
+        let ids: Vec<i64> = movies
+            .index_range("idx_cert", Key::Number(13)..)
+            .iter()
+            .map(|row| row.id)
+            .collect();
+        assert_eq!(ids, vec![1, 4, 3]);
+    }
+
+    /// Tests looking rows up by ID.
+    #[test]
+    fn get_and_range() {
+        let db = load_database("database/test_data.json").unwrap();
+        let actors = &db.tables["actors"];
+
+        assert_eq!(actors.get(2).unwrap().columns["name"], "Carrie-Anne Moss");
+        assert!(actors.get(5).is_none());
+
+        let ids: Vec<i64> = actors.range(2..4).map(|row| row.id).collect();
+        assert_eq!(ids, vec![2, 3]);
+        let ids: Vec<i64> = actors.range(3..).map(|row| row.id).collect();
+        assert_eq!(ids, vec![3, 4]);
+    }
+
+    /// Tests that schemas fill in defaults and reject mismatched rows.
+    #[test]
+    fn schema_apply() {
+        let schema = Schema {
+            columns: vec![
+                ColumnDef {
+                    name: "title".to_string(),
+                    data_type: DataType::Text,
+                    not_null: true,
+                    default: None,
+                },
+                ColumnDef {
+                    name: "cert".to_string(),
+                    data_type: DataType::Integer,
+                    not_null: false,
+                    default: Some(Value::from(18)),
+                },
+            ],
+            primary_key: None,
+            open: false,
+        };
+
+        let mut columns = BTreeMap::from([("title".to_string(), Value::from("Heat"))]);
+        schema.apply(&mut columns).unwrap();
+        assert_eq!(columns["cert"], 18);
+
+        let mut columns = BTreeMap::from([
+            ("title".to_string(), Value::from("Heat")),
+            ("cert".to_string(), Value::Null),
+        ]);
+        schema.apply(&mut columns).unwrap();
+        assert_eq!(columns["cert"], Value::Null);
+
+        for columns in [
+            vec![("cert", Value::from(15))],
+            vec![("title", Value::Null)],
+            vec![("title", Value::from("Heat")), ("cert", Value::from("15"))],
+            vec![("title", Value::from("Heat")), ("year", Value::from(1995))],
+        ] {
+            let mut columns = columns
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect();
+            assert!(schema.apply(&mut columns).is_err());
+        }
+    }
+
+    /// Tests that schemas are written to and read back from the database file.
+    #[test]
+    fn schema_round_trip() {
+        let mut db = load_database("database/test_data.json").unwrap();
+        let schema = Schema {
+            columns: vec![ColumnDef {
+                name: "rating".to_string(),
+                data_type: DataType::Integer,
+                not_null: true,
+                default: Some(Value::from(0)),
+            }],
+            primary_key: Some("review_id".to_string()),
+            open: false,
+        };
+        db.add_table("reviews", schema.clone()).unwrap();
+        assert!(db.add_table("reviews", schema.clone()).is_err());
+
+        let path = temporary_path("schema_round_trip.json");
+        db.path = Some(path.to_str().unwrap().to_string());
+        db.persist().unwrap();
+
+        let db = load_database(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.tables["reviews"].schema, Some(schema));
+        assert_eq!(db.tables["reviews"].key_column(), "review_id");
+        assert!(db.tables["reviews"].rows.is_empty());
+        assert_eq!(db.tables["movies"].schema, None);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    /// Tests inferring schemas from the rows of tables.
+    #[test]
+    fn infer_schema() {
+        let path = temporary_path("infer_schema.json");
+        fs::write(
+            &path,
+            r#"{
+                "albums": [
+                    {"id": 1, "title": "Abbey Road", "rating": 4, "year": 1969},
+                    {"id": 2, "title": "Thriller", "rating": "8", "price": 9.99},
+                    {"id": 3, "title": "21", "rating": 6, "year": null}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let mut db = load_database(path.to_str().unwrap()).unwrap();
+        fs::remove_file(path).unwrap();
+
+        // Rows holding values the engine cannot query are kept, and only reported as warnings.
+        assert_eq!(db.tables["albums"].rows.len(), 3);
+        assert!(db.diagnostics.is_empty());
+        assert!(db.check_writable().is_ok());
+
+        let schema = db.schema("albums").unwrap();
+        let column = |name: &str| schema.columns.iter().find(|c| c.name == name).unwrap();
+        assert_eq!(schema.columns.len(), 3);
+        assert_eq!(column("title").data_type, DataType::Text);
+        assert!(column("title").not_null);
+        assert_eq!(column("rating").data_type, DataType::Integer);
+        assert!(column("rating").not_null);
+        assert_eq!(column("year").data_type, DataType::Integer);
+        assert!(!column("year").not_null);
+
+        let warnings: Vec<String> = db.warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "albums.price: has 1 REAL value(s), which cannot be queried",
+                "albums.price: has no INTEGER or TEXT values, so its type is unknown",
+                "albums.rating: mixes 2 INTEGER and 1 TEXT values, inferred as INTEGER",
+            ]
+        );
+        assert_eq!(db.tables["albums"].infer_schema("albums").1.len(), 3);
+
+        let columns = BTreeMap::from([("price".to_string(), Value::from(12))]);
+        db.tables
+            .get_mut("albums")
+            .unwrap()
+            .add_row(4, columns)
+            .unwrap();
+        let schema = db.schema("albums").unwrap();
+        assert_eq!(schema.columns[0].name, "price");
+        assert_eq!(schema.columns[0].data_type, DataType::Integer);
+        assert!(!schema.columns[1].not_null);
+        assert!(db.schema("missing").is_none());
+    }
+
+    /// Tests that malformed tables and rows are reported, or fail a strict load.
+    #[test]
+    fn malformed_rows() {
+        let path = temporary_path("malformed_rows.json");
+        fs::write(
+            &path,
+            r#"{
+                "$schema": {"movies": {"columns": [{"name": "title", "type": "TEXT"}]}},
+                "movies": [
+                    {"id": 1, "title": "The Matrix"},
+                    "Titanic",
+                    {"title": "Alien"},
+                    {"id": "4", "title": "Heat"},
+                    {"id": 1, "title": "Up"},
+                    {"id": 6, "title": 1984}
+                ],
+                "actors": {"id": 1}
+            }"#,
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+
+        let db = load_database(path).unwrap();
+        assert_eq!(db.tables["movies"].rows.len(), 2);
+        assert_eq!(
+            db.tables["movies"].get(2).unwrap().columns["title"],
+            "Alien"
+        );
+        assert!(!db.tables.contains_key("actors"));
+
+        let diagnostics: Vec<String> = db.diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                "actors: table is not an array of rows",
+                "movies[1]: row is not an object",
+                r#"movies[3]: id "4" is not an integer"#,
+                "movies[4]: Row 1 already exists",
+                "movies[5]: Column title expects TEXT but got 1984",
+            ]
+        );
+
+        let error = load_database_with_mode(path, LoadMode::Strict).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "actors: table is not an array of rows");
+
+        fs::write(path, "[]").unwrap();
+        assert!(load_database(path).is_err());
+        fs::remove_file(path).unwrap();
+
+        assert!(load_database_with_mode("database/test_data.json", LoadMode::Strict).is_ok());
+    }
+
+    /// Tests that a saved database is loaded back unchanged, and that saving replaces the file.
+    #[test]
+    fn save_round_trip() {
+        let path = temporary_path("save_round_trip.json");
+        let path = path.to_str().unwrap();
+        fs::write(path, "{}").unwrap();
+
+        let mut db = load_database("database/test_data.json").unwrap();
+        db.create_index("idx_cert", "movies", "cert").unwrap();
+        let schema = Schema {
+            columns: vec![ColumnDef {
+                name: "text".to_string(),
+                data_type: DataType::Text,
+                not_null: false,
+                default: None,
+            }],
+            primary_key: Some("review_id".to_string()),
+            open: false,
+        };
+        db.add_table("reviews", schema).unwrap();
+        db.create_index("idx_text", "reviews", "text").unwrap();
+        let movies = db.tables.get_mut("movies").unwrap();
+        let columns = BTreeMap::from([
+            ("title".to_string(), Value::from("Heat")),
+            ("cert".to_string(), Value::Null),
+            ("score".to_string(), serde_json::json!(8.3)),
+            ("tags".to_string(), serde_json::json!(["crime", "heist"])),
+        ]);
+        movies.add_row(-1, columns).unwrap();
+        db.tables.insert("empty".to_string(), Table::new());
+
+        save_database(&db, path).unwrap();
+        let loaded = load_database(path).unwrap();
+        assert_eq!(loaded.to_json(), db.to_json());
+        assert!(loaded.diagnostics.is_empty());
+        assert_eq!(loaded.tables["movies"].index_on("cert"), Some("idx_cert"));
+        assert_eq!(loaded.tables["reviews"].index_on("text"), Some("idx_text"));
+        assert!(loaded.tables["empty"].rows.is_empty());
+
+        let saved = fs::read_to_string(path).unwrap();
+        save_database(&loaded, path).unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), saved);
+
+        let directory = std::path::Path::new(path).parent().unwrap();
+        let leftovers = fs::read_dir(directory).unwrap().any(|entry| {
+            let name = entry.unwrap().file_name();
+            let name = name.to_string_lossy();
+            name.starts_with(".simple_sql_engine_") && name.contains("save_round_trip")
+        });
+        assert!(!leftovers);
+        fs::remove_file(path).unwrap();
+
+        assert!(save_database(&db, "/nonexistent/directory/db.json").is_err());
+    }
+
+    /// Tests loading a table from an NDJSON file.
+    #[test]
+    fn ndjson_table() {
+        let path = temporary_path("events.ndjson");
+        fs::write(
+            &path,
+            "{\"id\": 2, \"kind\": \"click\"}\n\n{\"kind\": \"view\"}\r\n[1, 2]\n{\"kind\": \n{\"id\": 2, \"kind\": 7}\n{\"kind\": 7}",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+
+        let mut db = load_database("database/test_data.json").unwrap();
+        db.load_ndjson_table("events", path, LoadMode::Lenient)
+            .unwrap();
+        let events = &db.tables["events"];
+        assert_eq!(events.rows.len(), 3);
+        assert_eq!(events.get(3).unwrap().columns["kind"], "view");
+        assert_eq!(events.get(4).unwrap().columns["kind"], 7);
+
+        let diagnostics: Vec<String> = db.diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[0], "events[3]: row is not an object");
+        assert!(diagnostics[1].starts_with("events[4]: line is not valid JSON"));
+        assert_eq!(diagnostics[2], "events[5]: Row 2 already exists");
+        assert_eq!(db.warnings.last().unwrap().table_name, "events");
+
+        assert!(db.table_mut("events").is_err());
+        assert!(db.drop_table("events").is_err());
+        db.path = None;
+        for sql in [
+            "INSERT INTO events (kind) VALUES ('hacked')",
+            "UPDATE events SET kind = 'hacked'",
+            "DELETE FROM events WHERE events.id = 2",
+        ] {
+            let statement = crate::parser::parse_statement(sql).unwrap();
+            let error = crate::engine::execute_statement(statement, &mut db).unwrap_err();
+            assert!(
+                error.ends_with("changes are not written back to that file"),
+                "{}",
+                error
+            );
+        }
+        assert_eq!(db.tables["events"].get(2).unwrap().columns["kind"], "click");
+        assert!(db.to_json().get("events").is_none());
+        assert!(db.check_writable().is_ok());
+
+        assert!(db
+            .load_ndjson_table("events", path, LoadMode::Lenient)
+            .is_err());
+        let error = db
+            .load_ndjson_table("events2", path, LoadMode::Strict)
+            .unwrap_err();
+        assert_eq!(error.to_string(), "events2[3]: row is not an object");
+        assert!(!db.tables.contains_key("events2"));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    /// Tests loading a directory of files as a database.
+    #[test]
+    fn directory() {
+        let dir = temporary_path("directory");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("movies.json"),
+            r#"[{"id": 1, "title": "Heat"}, 7]"#,
+        )
+        .unwrap();
+        fs::write(dir.join("actors.CSV"), "id,name\n1,Al Pacino\n").unwrap();
+        fs::write(
+            dir.join("roles.ndjson"),
+            "{\"movieID\": 1, \"actorID\": 1}\n",
+        )
+        .unwrap();
+        fs::write(dir.join("movies.tsv"), "id\n1\n").unwrap();
+        fs::write(dir.join("bad-name.json"), "[]").unwrap();
+        fs::write(dir.join("broken.csv"), "a,b\n1\n").unwrap();
+        fs::write(dir.join("notes.txt"), "not a table").unwrap();
+        let path = dir.to_str().unwrap();
+
+        let db = load_database(path).unwrap();
+        assert_eq!(
+            db.tables.keys().collect::<Vec<_>>(),
+            vec!["actors", "broken", "movies", "roles"]
+        );
+        assert_eq!(
+            db.tables["actors"].get(1).unwrap().columns["name"],
+            "Al Pacino"
+        );
+        assert_eq!(db.tables["roles"].get(1).unwrap().columns["movieID"], 1);
+        assert_eq!(db.path, None);
+
+        let diagnostics: Vec<String> = db.diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(diagnostics.len(), 4);
+        assert_eq!(
+            diagnostics[0],
+            "bad-name: bad-name is not a valid table name"
+        );
+        assert_eq!(
+            diagnostics[1],
+            "broken[0]: line 2: expected 2 fields but found 1"
+        );
+        assert!(db.tables["broken"].rows.is_empty());
+        assert_eq!(db.directory.as_deref(), Some(path));
+        assert!(db.check_writable().is_err());
+        assert!(db.persist().is_err());
+        assert_eq!(diagnostics[2], "movies[1]: row is not an object");
+        assert!(diagnostics[3].contains("is a second file for the table"));
+
+        let error = load_database_with_mode(path, LoadMode::Strict).unwrap_err();
+        assert_eq!(error.to_string(), "bad-name is not a valid table name");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// Tests that IDs are unique, generated for rows without one and read from the declared key column.
+    #[test]
+    fn primary_key() {
+        let mut table = Table::new();
+        assert_eq!(table.next_id(), Some(1));
+        table.add_row(-3, BTreeMap::new()).unwrap();
+        table.add_row(7, BTreeMap::new()).unwrap();
+        assert!(table.add_row(7, BTreeMap::new()).is_err());
+        assert_eq!(table.next_id(), Some(8));
+        assert_eq!(table.key_column(), "id");
+
+        let path = temporary_path("primary_key.json");
+        fs::write(
+            &path,
+            r#"{
+                "$schema": {"albums": {"columns": [{"name": "id", "type": "TEXT"}], "primary_key": "album_id"}},
+                "albums": [{"id": "a"}, {"album_id": 5, "id": "b"}, {"album_id": null, "id": "c"}]
+            }"#,
+        )
+        .unwrap();
+        let db = load_database(path.to_str().unwrap()).unwrap();
+        fs::remove_file(path).unwrap();
+
+        let albums = &db.tables["albums"];
+        let ids: Vec<(i64, &Value)> = albums
+            .rows
+            .iter()
+            .map(|row| (row.id, &row.columns["id"]))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                (5, &Value::from("b")),
+                (6, &Value::from("a")),
+                (7, &Value::from("c"))
+            ]
+        );
+        assert_eq!(
+            albums.get(5).unwrap().to_json(albums.key_column()),
+            serde_json::json!({"album_id": 5, "id": "b"})
+        );
+    }
+
     /// Tests loading a database with an incorrect file name.
     ///
     /// This test should panic.
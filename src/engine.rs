@@ -1,333 +1,902 @@
-use std::cmp::max;
-use std::fmt::Write;
+use std::fmt;
+use std::io;
 
-use crate::database::Database;
-use crate::parser::{Column, Comparison, Const, Query, Value};
+use crate::database::{
+    check_values, load_database, ColumnDef, DataType, Database, Row, Schema, Table,
+    DEFAULT_KEY_COLUMN, MAIN_DATABASE,
+};
+use crate::output;
+use crate::parser::{
+    qualified_name, AlterAction, AlterTable, Column, ColumnDefinition, Comparison, Const,
+    CreateTable, Delete, Insert, Query, Statement, Update, Value, ValueTest,
+};
+use crate::plan::Plan;
+use crate::provider::{Filter, TableProvider};
 use std::collections::{BTreeMap, BTreeSet};
 
-/// Represents a view of the database that is generated from executing a parsed SQL query.
+/// Represents the outcome of executing a statement.
 #[derive(Debug)]
-pub struct View<'a> {
-    /// A vector of rows, where each row is represented as a `BTreeMap` of column names to values.
-    pub rows: Vec<BTreeMap<String, Value>>,
-    /// The parsed SQL query.
-    parsed_query: Query<'a>,
-    /// The database on which the query is executed.
-    database: Database,
+pub enum Outcome {
+    /// The rows produced by a query.
+    Rows(View),
+    /// The operator tree described by an `EXPLAIN` statement.
+    Plan(String),
+    /// A statement that produces no output completed successfully.
+    Done,
+    /// The number of rows changed by a statement.
+    Affected(usize),
 }
 
-impl<'a> View<'a> {
-    /// Executes a parsed SQL query on a database and returns a `View` object.
-    ///
-    /// # Arguments
-    ///
-    /// * `parsed_query` - The parsed SQL query.
-    /// * `database` - The database to execute the query on.
-    ///
-    /// # Returns
-    ///
-    /// A `View` object containing the result of the query.
-    pub fn execute(parsed_query: Query<'a>, database: Database) -> View<'a> {
-        let view = View {
-            rows: vec![],
-            parsed_query,
+/// Executes a parsed SQL statement on a database.
+///
+/// # Arguments
+///
+/// * `statement` - The parsed SQL statement.
+/// * `database` - The database to execute the statement on.
+///
+/// # Returns
+///
+/// A `Result` containing the outcome of the statement, or a message describing why it failed.
+pub fn execute_statement(statement: Statement, database: &mut Database) -> Result<Outcome, String> {
+    let parameter_count = statement.parameter_count();
+    if parameter_count > 0 {
+        return Err(format!(
+            "The statement has {} parameter(s) that are not bound",
+            parameter_count
+        ));
+    }
+
+    match statement {
+        Statement::Select(parsed_query) => {
+            Ok(Outcome::Rows(View::execute(parsed_query, database)?))
+        }
+        Statement::Explain {
+            query: parsed_query,
+            analyze,
+        } => Ok(Outcome::Plan(View::explain(
+            &parsed_query,
             database,
-        };
+            analyze,
+        )?)),
+        Statement::Attach(attach) => {
+            let attached = load_database(&attach.path)
+                .map_err(|e| format!("Cannot attach {}: {}", attach.path, e))?;
+            database.attach(&attach.database_name, attached)?;
+            Ok(Outcome::Done)
+        }
+        statement => {
+            // Statements that change the database fail before changing it if it cannot be
+            // written back, and leave it unchanged if writing it back fails. Each changes a
+            // single table, so only that table is kept to be restored.
+            database.check_writable()?;
+            let table_name = changed_table(&statement).to_string();
+            let table = database.tables.get(&table_name).cloned();
+            let outcome = execute_change(statement, database).and_then(|outcome| {
+                database.persist().map_err(|e| e.to_string())?;
+                Ok(outcome)
+            });
+            if outcome.is_err() {
+                match table {
+                    Some(table) => database.tables.insert(table_name, table),
+                    None => database.tables.remove(&table_name),
+                };
+            }
+            outcome
+        }
+    }
+}
 
-        view.from().joins().apply_where().select()
+/// Gets the name of the table a statement that changes the database changes.
+///
+/// # Arguments
+///
+/// * `statement` - The parsed SQL statement, which is not a query or an `ATTACH`.
+///
+/// # Returns
+///
+/// The name of the table, which may not exist yet.
+fn changed_table(statement: &Statement) -> &str {
+    match statement {
+        Statement::CreateIndex(create_index) => &create_index.table_name,
+        Statement::CreateTable(create_table) => &create_table.table_name,
+        Statement::Insert(insert) => &insert.table_name,
+        Statement::Update(update) => &update.table_name,
+        Statement::Delete(delete) => &delete.table_name,
+        Statement::DropTable(drop_table) => &drop_table.table_name,
+        Statement::AlterTable(alter_table) => &alter_table.table_name,
+        Statement::Select(_) | Statement::Explain { .. } | Statement::Attach(_) => {
+            unreachable!("execute_statement executes statements that do not change the database")
+        }
     }
+}
 
-    /// Populates the view with rows from the specified table in the `FROM` clause.
-    ///
-    /// # Returns
-    ///
-    /// A `View` object with rows from the specified table.
-    fn from(self) -> View<'a> {
-        let table_name = &self.parsed_query.from;
+/// Executes a statement that changes the tables of a database, without writing it back.
+///
+/// # Arguments
+///
+/// * `statement` - The parsed SQL statement, which is not a query or an `ATTACH`.
+/// * `database` - The database to execute the statement on.
+///
+/// # Returns
+///
+/// A `Result` containing the outcome of the statement, or a message describing why it failed.
+fn execute_change(statement: Statement, database: &mut Database) -> Result<Outcome, String> {
+    match statement {
+        Statement::CreateIndex(create_index) => {
+            database.create_index(
+                &create_index.index_name,
+                &create_index.table_name,
+                &create_index.column_name,
+            )?;
+            Ok(Outcome::Done)
+        }
+        Statement::CreateTable(create_table) => {
+            let schema = schema(&create_table)?;
+            database.add_table(&create_table.table_name, schema)?;
+            Ok(Outcome::Done)
+        }
+        Statement::Insert(insert) => Ok(Outcome::Affected(execute_insert(&insert, database)?)),
+        Statement::Update(update) => Ok(Outcome::Affected(execute_update(&update, database)?)),
+        Statement::Delete(delete) => Ok(Outcome::Affected(execute_delete(&delete, database)?)),
+        Statement::DropTable(drop_table) => {
+            database.drop_table(&drop_table.table_name)?;
+            Ok(Outcome::Done)
+        }
+        Statement::AlterTable(alter_table) => {
+            execute_alter_table(&alter_table, database)?;
+            Ok(Outcome::Done)
+        }
+        Statement::Select(_) | Statement::Explain { .. } | Statement::Attach(_) => {
+            unreachable!("execute_statement executes statements that do not change the database")
+        }
+    }
+}
+
+/// Checks that a query can be executed: its parameters are bound, and it is resolved by
+/// `resolve_query`.
+///
+/// # Arguments
+///
+/// * `query` - The parsed query.
+/// * `database` - The database the query will be executed on.
+///
+/// # Returns
+///
+/// A `Result` containing the query with its columns resolved, or a message describing the
+/// first problem found.
+fn check_query(query: &Query, database: &Database) -> Result<Query, String> {
+    let parameter_count = query.parameter_count();
+    if parameter_count > 0 {
+        return Err(format!(
+            "The statement has {} parameter(s) that are not bound",
+            parameter_count
+        ));
+    }
+    resolve_query(query, database)
+}
+
+/// Checks that a query reads from tables that exist, and only refers to the columns of
+/// the tables it reads from.
+///
+/// A table may be named with or without its database (`main.movies` or `movies`), so each
+/// column is resolved to the table it refers to and renamed after the `FROM` or `JOIN` clause
+/// that reads the table, which is how the rows of the query are keyed.
+///
+/// # Arguments
+///
+/// * `query` - The parsed query.
+/// * `database` - The database the query will be executed on.
+///
+/// # Returns
+///
+/// A `Result` containing the query with its columns resolved, or a message describing the
+/// first problem found.
+fn resolve_query(query: &Query, database: &Database) -> Result<Query, String> {
+    let clauses = std::iter::once((&query.database, &query.from)).chain(
+        query
+            .joins
+            .iter()
+            .map(|join| (&join.database, &join.table_name)),
+    );
+    let mut tables = vec![];
+    for (database_name, table_name) in clauses {
+        let name = qualified_name(database_name.as_deref(), table_name);
+        let resolved = database
+            .resolve_table(&name)
+            .ok_or_else(|| format!("Table {} does not exist", name))?;
+        tables.push((resolved, database_name.clone(), table_name.clone()));
+    }
 
-        View {
-            rows: self.table_to_vec(table_name.to_owned()),
-            parsed_query: self.parsed_query,
-            database: self.database,
+    let resolve = |column: &mut Column| -> Result<(), String> {
+        let (_, database_name, table_name) = database
+            .resolve_table(&column.qualified_table())
+            .and_then(|resolved| tables.iter().find(|(name, ..)| *name == resolved))
+            .ok_or_else(|| {
+                format!(
+                    "Column {} refers to a table the query does not read from",
+                    column.qualified_name()
+                )
+            })?;
+        column.database = database_name.clone();
+        column.table_name = table_name.clone();
+        Ok(())
+    };
+
+    let mut query = query.clone();
+    for column in &mut query.select {
+        resolve(column)?;
+    }
+    let conditions = query
+        .joins
+        .iter_mut()
+        .map(|join| &mut join.on)
+        .chain(query.where_clause.as_mut());
+    for condition in conditions {
+        for value in [&mut condition.left, &mut condition.right] {
+            if let Value::Column(column) = value {
+                resolve(column)?;
+            }
         }
     }
+    for order_by in &mut query.order_by {
+        resolve(&mut order_by.column)?;
+    }
+    Ok(query)
+}
 
-    /// Processes the `JOIN` clauses and merges rows from the joined tables.
-    ///
-    /// # Returns
-    ///
-    /// A `View` object with rows merged according to the `JOIN` clauses.
-    fn joins(self) -> View<'a> {
-        let mut rows = self.rows.clone();
-
-        for join in &self.parsed_query.joins {
-            let mut new_rows: Vec<BTreeMap<String, Value>> = vec![];
-            let join_table = self.table_to_vec(join.table_name.clone());
-
-            for row in &rows {
-                for join_row in &join_table {
-                    let left_value = self
-                        .get_column_value(row, &join.on.left)
-                        .or(self.get_column_value(join_row, &join.on.left))
-                        .unwrap();
-                    let right_value = self
-                        .get_column_value(row, &join.on.right)
-                        .or(self.get_column_value(join_row, &join.on.right))
-                        .unwrap();
-
-                    if self.compare_values(&left_value, &join.on.comparison, &right_value) {
-                        let mut new_row = row.clone();
-                        for (k, v) in join_row {
-                            new_row.insert(k.clone(), v.clone());
-                        }
-                        new_rows.push(new_row);
-                    }
-                }
+/// Builds the schema defined by a `CREATE TABLE` statement.
+///
+/// The definition of the primary-key column, which is the one marked `PRIMARY KEY` or else
+/// `id`, only describes the ID every row has, and is left out of the columns.
+///
+/// # Arguments
+///
+/// * `create_table` - The parsed `CREATE TABLE` statement.
+///
+/// # Returns
+///
+/// A `Result` containing the schema, or a message describing why the definitions are invalid.
+fn schema(create_table: &CreateTable) -> Result<Schema, String> {
+    let mut keys = create_table.columns.iter().filter(|c| c.primary_key);
+    let key_column = match (keys.next(), keys.next()) {
+        (Some(_), Some(_)) => return Err("A table can have only one PRIMARY KEY".to_string()),
+        (Some(key), None) => key.name.as_str(),
+        (None, _) => DEFAULT_KEY_COLUMN,
+    };
+
+    let mut columns: Vec<ColumnDef> = vec![];
+    let mut names = BTreeSet::new();
+    for definition in &create_table.columns {
+        if !names.insert(&definition.name) {
+            return Err(format!("Column {} is defined twice", definition.name));
+        }
+        if definition.name == key_column {
+            if definition.data_type != DataType::Integer {
+                return Err(format!("Column {} must be an INTEGER", key_column));
             }
+            if definition.default.is_some() {
+                return Err(format!("Column {} cannot have a default", key_column));
+            }
+            continue;
+        }
+        columns.push(column_def(definition)?);
+    }
+
+    Ok(Schema {
+        columns,
+        primary_key: (key_column != DEFAULT_KEY_COLUMN).then(|| key_column.to_string()),
+        open: false,
+    })
+}
 
-            rows = new_rows;
+/// Builds the definition of a column that is not the primary key.
+///
+/// # Arguments
+///
+/// * `definition` - The parsed column definition.
+///
+/// # Returns
+///
+/// A `Result` containing the column definition, or a message describing why it is invalid.
+fn column_def(definition: &ColumnDefinition) -> Result<ColumnDef, String> {
+    let default = definition.default.as_ref().map(Const::to_serde_value);
+    match &default {
+        Some(serde_json::Value::Null) if definition.not_null => {
+            return Err(format!("Column {} cannot default to NULL", definition.name));
         }
+        Some(value) if !value.is_null() && !definition.data_type.accepts(value) => {
+            return Err(format!(
+                "Default of column {} is not {}",
+                definition.name, definition.data_type
+            ));
+        }
+        _ => {}
+    }
 
-        View {
-            rows,
-            parsed_query: self.parsed_query,
-            database: self.database,
+    Ok(ColumnDef {
+        name: definition.name.clone(),
+        data_type: definition.data_type,
+        not_null: definition.not_null,
+        default,
+    })
+}
+
+/// Changes the columns of a table as described by an `ALTER TABLE` statement.
+///
+/// # Arguments
+///
+/// * `alter_table` - The parsed `ALTER TABLE` statement.
+/// * `database` - The database containing the table.
+///
+/// # Returns
+///
+/// A `Result` indicating success, or a message describing why the statement failed.
+fn execute_alter_table(alter_table: &AlterTable, database: &mut Database) -> Result<(), String> {
+    let table = database.table_mut(&alter_table.table_name)?;
+
+    match &alter_table.action {
+        AlterAction::Add(definition) => {
+            if definition.primary_key {
+                return Err("A PRIMARY KEY column cannot be added to a table".to_string());
+            }
+            table.add_column(column_def(definition)?)
         }
+        AlterAction::Drop(column) => table.drop_column(column),
+        AlterAction::Rename { from, to } => table.rename_column(from, to),
     }
+}
 
-    /// Filters rows based on the `WHERE` clause.
-    ///
-    /// # Returns
-    ///
-    /// A `View` object with rows filtered according to the `WHERE` clause.
-    fn apply_where(self) -> View<'a> {
-        let mut rows = self.rows.clone();
-        if let Some(where_clause) = &self.parsed_query.where_clause {
-            rows.retain(|row| {
-                where_clause.left.get_const();
-                let left_value = self.get_column_value(row, &where_clause.left).unwrap();
-                let right_value = self.get_column_value(row, &where_clause.right).unwrap();
-                self.compare_values(&left_value, &where_clause.comparison, &right_value)
-            });
+/// Adds the rows of an `INSERT` statement to a table.
+///
+/// Every row is checked before any is added, so a failing statement leaves the table unchanged.
+/// Rows that do not give a value for the primary-key column are given the next free ID.
+///
+/// # Arguments
+///
+/// * `insert` - The parsed `INSERT` statement.
+/// * `database` - The database containing the table.
+///
+/// # Returns
+///
+/// A `Result` containing the number of rows added, or a message describing why the statement failed.
+fn execute_insert(insert: &Insert, database: &mut Database) -> Result<usize, String> {
+    let table = database.table_mut(&insert.table_name)?;
+
+    let key_column = table.key_column();
+    let mut rows: BTreeMap<i64, BTreeMap<String, serde_json::Value>> = BTreeMap::new();
+    for values in &insert.rows {
+        let mut id = None;
+        let mut columns = BTreeMap::new();
+        for (column, value) in insert.columns.iter().zip(values) {
+            let Value::Const(value) = value else {
+                return Err(format!("Invalid value {}", value));
+            };
+            if column == key_column {
+                id = match value {
+                    Const::Number(n) => Some(*n),
+                    Const::Null => None,
+                    Const::String(_) => {
+                        return Err(format!("Invalid {} {}", key_column, value));
+                    }
+                };
+            } else {
+                columns.insert(column.clone(), value.to_serde_value());
+            }
         }
-        View {
-            rows,
-            parsed_query: self.parsed_query,
-            database: self.database,
+
+        let id = match id {
+            Some(id) => id,
+            None => {
+                // The rows of this statement are not in the table yet, so they are
+                // taken into account separately.
+                let greatest = table.rows.last().map(|row| row.id);
+                match greatest.max(rows.keys().next_back().copied()) {
+                    Some(id) => id.checked_add(1),
+                    None => Some(1),
+                }
+                .ok_or_else(|| format!("Table {} has no IDs left", insert.table_name))?
+            }
+        };
+        table
+            .check_row(&mut columns)
+            .and_then(|()| check_values(&columns))
+            .map_err(|e| format!("Row {}: {}", id, e))?;
+        if table.get(id).is_some() || rows.contains_key(&id) {
+            return Err(format!(
+                "Row {} already exists in table {}",
+                id, insert.table_name
+            ));
         }
+        rows.insert(id, columns);
     }
 
-    /// Selects the specified columns and constructs the final result set.
-    ///
-    /// # Returns
-    ///
-    /// A `View` object with the selected columns.
-    fn select(self) -> View<'a> {
-        let column_names: BTreeSet<String> = self
-            .parsed_query
-            .select
-            .iter()
-            .map(|c| format!("{}.{}", c.table_name, c.column_name))
-            .collect();
+    let count = rows.len();
+    for (id, columns) in rows {
+        table.add_row(id, columns)?;
+    }
+    Ok(count)
+}
 
-        let selected_rows = self
-            .rows
-            .into_iter()
-            .map(|x| {
-                x.into_iter()
-                    .filter(|(k, _)| column_names.contains(k))
-                    .collect()
+/// Changes the rows of a table that match the `WHERE` clause of an `UPDATE` statement.
+///
+/// Every assigned column must be a column of the table, and be assigned once. Assigned values
+/// that refer to columns are resolved as the columns of the `WHERE` clause are, and are taken
+/// from the row before it is changed, as `NULL` if the row has no value for them.
+///
+/// # Arguments
+///
+/// * `update` - The parsed `UPDATE` statement.
+/// * `database` - The database containing the table.
+///
+/// # Returns
+///
+/// A `Result` containing the number of rows changed, or a message describing why the statement failed.
+fn execute_update(update: &Update, database: &mut Database) -> Result<usize, String> {
+    let table_name = &update.table_name;
+    let ids = matching_rows(database, table_name, update.where_clause.as_ref())?;
+    let table = &database.tables[table_name];
+    let key_column = table.key_column();
+    if update
+        .assignments
+        .iter()
+        .any(|(column, _)| column == key_column)
+    {
+        return Err(format!("The {} of a row cannot be updated", key_column));
+    }
+    let mut targets = BTreeSet::new();
+    for (column, _) in &update.assignments {
+        if !table.has_column(column) {
+            return Err(format!("Column {}.{} is not defined", table_name, column));
+        }
+        if !targets.insert(column) {
+            return Err(format!("Column {} is assigned more than once", column));
+        }
+    }
+    let mut assignments = update.assignments.clone();
+    for (_, value) in &mut assignments {
+        if let Value::Column(column) = value {
+            resolve_column(column, table_name, table, database)?;
+        }
+    }
+
+    let changes = ids
+        .into_iter()
+        .map(|id| {
+            let row = table.get(id).unwrap();
+            let values = row_to_map(table_name, key_column, row);
+
+            // Only the assigned values are checked, as the other columns of the row may hold
+            // values the engine cannot query, which are kept as they were loaded.
+            let assigned: BTreeMap<String, serde_json::Value> = assignments
+                .iter()
+                .map(|(column, value)| {
+                    let value = get_column_value(&values, value).unwrap_or(Const::Null);
+                    (column.clone(), value.to_serde_value())
+                })
+                .collect();
+            let mut columns = row.columns.clone();
+            columns.extend(assigned.clone());
+            check_values(&assigned)
+                .and_then(|()| table.check_row(&mut columns))
+                .map_err(|e| format!("Row {}: {}", id, e))?;
+            Ok((id, columns))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let table = database.tables.get_mut(table_name).unwrap();
+    let count = changes.len();
+    for (id, columns) in changes {
+        table.remove_row(id);
+        table.add_row(id, columns)?;
+    }
+    Ok(count)
+}
+
+/// Removes the rows of a table that match the `WHERE` clause of a `DELETE` statement.
+///
+/// # Arguments
+///
+/// * `delete` - The parsed `DELETE` statement.
+/// * `database` - The database containing the table.
+///
+/// # Returns
+///
+/// A `Result` containing the number of rows removed, or a message describing why the statement failed.
+fn execute_delete(delete: &Delete, database: &mut Database) -> Result<usize, String> {
+    let ids = matching_rows(database, &delete.table_name, delete.where_clause.as_ref())?;
+
+    let table = database.tables.get_mut(&delete.table_name).unwrap();
+    for id in &ids {
+        table.remove_row(*id);
+    }
+    Ok(ids.len())
+}
+
+/// Finds the rows of a table that match an optional `WHERE` clause.
+///
+/// The columns of the clause must be columns of the table, which they may name with or without
+/// its database (`main.movies` or `movies`), as the columns of a query are resolved by
+/// `resolve_query`.
+///
+/// # Arguments
+///
+/// * `database` - The database containing the table.
+/// * `table_name` - The name of the table.
+/// * `where_clause` - The condition rows must satisfy, or `None` to match every row.
+///
+/// # Returns
+///
+/// A `Result` containing the IDs of the matching rows, or a message describing why the
+/// rows could not be searched, such as a table that statements cannot change.
+fn matching_rows(
+    database: &mut Database,
+    table_name: &str,
+    where_clause: Option<&ValueTest>,
+) -> Result<Vec<i64>, String> {
+    database.table_mut(table_name)?;
+    let table = &database.tables[table_name];
+
+    let where_clause = where_clause
+        .map(|condition| {
+            let mut condition = condition.clone();
+            for value in [&mut condition.left, &mut condition.right] {
+                if let Value::Column(column) = value {
+                    resolve_column(column, table_name, table, database)?;
+                }
+            }
+            Ok::<_, String>(condition)
+        })
+        .transpose()?;
+
+    Ok(table
+        .rows
+        .iter()
+        .filter(|row| {
+            where_clause.as_ref().is_none_or(|condition| {
+                matches(&row_to_map(table_name, table.key_column(), row), condition)
             })
-            .collect();
+        })
+        .map(|row| row.id)
+        .collect())
+}
 
-        View {
-            rows: selected_rows,
-            parsed_query: self.parsed_query,
-            database: self.database,
-        }
+/// Checks that a column refers to a column of the table a statement changes, and names it
+/// after the table, unqualified, as the rows of the table are keyed.
+///
+/// # Arguments
+///
+/// * `column` - The column, which is renamed.
+/// * `table_name` - The name of the table, which belongs to `database`.
+/// * `table` - The table.
+/// * `database` - The database containing the table.
+///
+/// # Returns
+///
+/// A `Result` indicating success, or a message if the column refers to another table or to a
+/// column the table does not have.
+fn resolve_column(
+    column: &mut Column,
+    table_name: &str,
+    table: &Table,
+    database: &Database,
+) -> Result<(), String> {
+    if database.resolve_table(&column.qualified_table())
+        != database.resolve_table(&qualified_name(Some(MAIN_DATABASE), table_name))
+    {
+        return Err(format!(
+            "Column {} refers to a table the query does not read from",
+            column.qualified_name()
+        ));
     }
+    if !table.has_column(&column.column_name) {
+        return Err(format!("Column {} is not defined", column.qualified_name()));
+    }
+    column.database = None;
+    column.table_name = table_name.to_string();
+    Ok(())
+}
+
+/// Represents a view of the database that is generated from executing a parsed SQL query.
+#[derive(Debug)]
+pub struct View {
+    /// The selected columns, in the order of the `SELECT` clause. The qualified name of each
+    /// column is the key of its value in the rows.
+    pub columns: Vec<Column>,
+    /// A vector of rows, where each row is represented as a `BTreeMap` of column names to values.
+    pub rows: Vec<BTreeMap<String, Value>>,
+}
 
-    /// Gets the value of a column in a row.
+impl View {
+    /// Executes a parsed SQL query on a database and returns a `View` object.
+    ///
+    /// The query is checked first, as `execute_statement` checks it: its parameters must be
+    /// bound, and it must read from tables that exist and only refer to their columns.
     ///
     /// # Arguments
     ///
-    /// * `row` - A reference to the row.
-    /// * `value` - The column value.
+    /// * `parsed_query` - The parsed SQL query.
+    /// * `database` - The database to execute the query on.
     ///
     /// # Returns
     ///
-    /// An `Option` containing the value if it exists, otherwise `None`.
-    fn get_column_value(&self, row: &BTreeMap<String, Value>, value: &Value) -> Option<Const> {
-        if let Value::Const(c) = value {
-            return Some(c.clone());
-        };
+    /// A `Result` containing a `View` object with the result of the query, or a message
+    /// describing why the query cannot be executed.
+    pub fn execute(parsed_query: Query, database: &Database) -> Result<View, String> {
+        let query = check_query(&parsed_query, database)?;
+        let rows = Plan::new(&query, database).execute(database)?;
 
-        let table_name = value.get_table_name();
-        let column_name = value.get_column_name();
-
-        let key = &format!("{}.{}", table_name, column_name);
-        match row.get(key) {
-            None => None,
-            value => value.unwrap().get_const(),
-        }
+        Ok(View {
+            columns: query.select,
+            rows,
+        })
     }
 
-    /// Compares two values based on the specified comparison operator.
+    /// Describes how a parsed SQL query would be executed on a database, after checking it as
+    /// `View::execute` does.
     ///
     /// # Arguments
     ///
-    /// * `left` - The left value to compare.
-    /// * `comparison` - The comparison operator.
-    /// * `right` - The right value to compare.
+    /// * `parsed_query` - The parsed SQL query.
+    /// * `database` - The database to execute the query on.
+    /// * `analyze` - Whether to execute the query and report per-operator measurements.
     ///
     /// # Returns
     ///
-    /// A boolean indicating the result of the comparison.
-    fn compare_values(&self, left: &Const, comparison: &Comparison, right: &Const) -> bool {
-        match (left, right) {
-            (Const::Number(left), Const::Number(right)) => match comparison {
-                Comparison::Eq => left == right,
-                Comparison::Gt => left > right,
-                Comparison::Lt => left < right,
-                Comparison::Le => left <= right,
-                Comparison::Ge => left >= right,
-                Comparison::Ne => left != right,
-            },
-            (Const::String(left), Const::String(right)) => match comparison {
-                Comparison::Eq => left == right,
-                Comparison::Gt => left > right,
-                Comparison::Lt => left < right,
-                Comparison::Le => left <= right,
-                Comparison::Ge => left >= right,
-                Comparison::Ne => left != right,
-            },
-            _ => false,
-        }
-    }
-
-    /// Converts a table to a vector of rows, each row being a `BTreeMap` of column names and values.
-    ///
-    /// # Arguments
-    ///
-    /// * `table_name` - The name of the table.
+    /// A `Result` containing the operator tree, one operator per line, or a message describing
+    /// why the query cannot be executed.
+    pub fn explain(
+        parsed_query: &Query,
+        database: &Database,
+        analyze: bool,
+    ) -> Result<String, String> {
+        let query = check_query(parsed_query, database)?;
+        let plan = Plan::new(&query, database);
+
+        Ok(if analyze {
+            let (_, analysis) = plan.analyze(database)?;
+            plan.explain(Some(&analysis))
+        } else {
+            plan.explain(None)
+        })
+    }
+
+    /// Gets the names of the selected columns, in the order of the `SELECT` clause.
     ///
     /// # Returns
     ///
-    /// A vector of rows.
-    fn table_to_vec(&self, table_name: String) -> Vec<BTreeMap<String, Value>> {
-        let table = self.database.tables.get(&table_name).unwrap();
-        table
-            .rows
-            .iter()
-            .map(|row| {
-                let mut columns: BTreeMap<String, Value> = row
-                    .columns
-                    .iter()
-                    .map(|(k, v)| {
-                        let v = Value::from_serde_value(v);
-                        (format!("{}.{}", table_name, k), v)
-                    })
-                    .collect();
-                columns.insert(
-                    format!("{table_name}.id"),
-                    Value::Const(Const::Number(row.id as i64)),
-                );
-
-                columns
-            })
-            .collect()
+    /// The names of the columns, qualified by their tables, as they key the rows.
+    pub fn column_names(&self) -> Vec<String> {
+        self.columns.iter().map(Column::qualified_name).collect()
     }
 
-    /// Displays the contents of the `View` in a table format.
+    /// Displays the contents of the `View` in a table format on standard output.
     pub fn display(&self) {
-        if self.rows.is_empty() {
-            println!("No data to display");
-            return;
-        }
+        output::write_table(self, &mut io::stdout().lock()).unwrap();
+    }
+}
 
-        // Collect all column names
-        let columns: Vec<&String> = self.rows[0].keys().collect();
+/// Gets the value of a column in a row.
+///
+/// # Arguments
+///
+/// * `row` - A reference to the row.
+/// * `value` - The column value.
+///
+/// # Returns
+///
+/// An `Option` containing the value if it exists, otherwise `None`.
+pub(crate) fn get_column_value(row: &BTreeMap<String, Value>, value: &Value) -> Option<Const> {
+    if let Value::Const(c) = value {
+        return Some(c.clone());
+    };
 
-        // Determine the width of each column
-        let mut column_widths = BTreeMap::new();
-        for column in &columns {
-            column_widths.insert(*column, column.len());
-        }
-        for row in &self.rows {
-            for (column, value) in row {
-                let width = max(column_widths[column], value.to_string().len());
-                column_widths.insert(column, width);
-            }
-        }
+    let table_name = value.get_table_name();
+    let column_name = value.get_column_name();
 
-        // Print header
-        let mut header = String::new();
-        for column in &columns {
-            let width = column_widths[column];
-            write!(header, "{:width$} | ", column, width = width).unwrap();
-        }
-        println!("{}", header);
+    let key = &format!("{}.{}", table_name, column_name);
+    match row.get(key) {
+        None => None,
+        value => value.unwrap().get_const(),
+    }
+}
 
-        // Print separator
-        let mut separator: String = columns
-            .iter()
-            .enumerate()
-            .map(|column| {
-                "-".repeat(
-                    column_widths[column.1] + 1 + {
-                        if column.0 != 0 {
-                            1
-                        } else {
-                            0
-                        }
-                    },
-                )
-            })
-            .collect::<Vec<_>>()
-            .join("|");
-        separator.push('|');
-        println!("{}", separator);
-
-        // Print rows
-        for row in &self.rows {
-            let mut row_str = String::new();
-            for column in &columns {
-                let width = column_widths[column];
-                let value = row.get(*column).unwrap().to_string();
-                write!(row_str, "{:width$} | ", value, width = width).unwrap();
-            }
-            println!("{}", row_str);
+/// Checks whether a row satisfies a condition.
+///
+/// # Arguments
+///
+/// * `row` - A reference to the row.
+/// * `condition` - The condition, such as the one of a `WHERE` clause.
+///
+/// # Returns
+///
+/// A boolean indicating whether the row satisfies the condition. A row missing a column
+/// the condition refers to does not satisfy it.
+pub(crate) fn matches(row: &BTreeMap<String, Value>, condition: &ValueTest) -> bool {
+    match (
+        get_column_value(row, &condition.left),
+        get_column_value(row, &condition.right),
+    ) {
+        (Some(left), Some(right)) => compare_values(&left, &condition.comparison, &right),
+        _ => false,
+    }
+}
+
+/// Compares two values based on the specified comparison operator.
+///
+/// # Arguments
+///
+/// * `left` - The left value to compare.
+/// * `comparison` - The comparison operator.
+/// * `right` - The right value to compare.
+///
+/// # Returns
+///
+/// A boolean indicating the result of the comparison.
+pub(crate) fn compare_values(left: &Const, comparison: &Comparison, right: &Const) -> bool {
+    match (left, right) {
+        (Const::Number(left), Const::Number(right)) => match comparison {
+            Comparison::Eq => left == right,
+            Comparison::Gt => left > right,
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Ge => left >= right,
+            Comparison::Ne => left != right,
+        },
+        (Const::String(left), Const::String(right)) => match comparison {
+            Comparison::Eq => left == right,
+            Comparison::Gt => left > right,
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Ge => left >= right,
+            Comparison::Ne => left != right,
+        },
+        _ => false,
+    }
+}
+
+/// Converts a table to a vector of rows, each row being a `BTreeMap` of column names and values.
+///
+/// # Arguments
+///
+/// * `database` - The database containing the table.
+/// * `table_name` - The name of the table, or of a table provider, whose rows are read from it.
+///
+/// # Returns
+///
+/// A `Result` containing a vector of rows, or a message describing why the rows of a table
+/// provider could not be read.
+pub(crate) fn table_to_vec(
+    database: &Database,
+    table_name: &str,
+) -> Result<Vec<BTreeMap<String, Value>>, String> {
+    let Some(table) = database.table(table_name) else {
+        let provider = database
+            .provider(table_name)
+            .ok_or_else(|| format!("Table {} does not exist", table_name))?;
+        return provider_to_vec(table_name, provider, None);
+    };
+    Ok(table
+        .rows
+        .iter()
+        .map(|row| row_to_map(table_name, table.key_column(), row))
+        .collect())
+}
+
+/// Reads the rows of a table provider, each row being a `BTreeMap` of column names and values.
+///
+/// The rows are checked against the schema of the provider, as the rows of a table are, and
+/// the columns they leave out take their default value. Their IDs must be unique.
+///
+/// # Arguments
+///
+/// * `table_name` - The name the provider is queried under.
+/// * `provider` - The table provider.
+/// * `filter` - The condition the provider applies to its rows, if it supports it.
+///
+/// # Returns
+///
+/// A `Result` containing a vector of rows, or a message describing the first row that does
+/// not match the schema or repeats an ID.
+pub(crate) fn provider_to_vec(
+    table_name: &str,
+    provider: &dyn TableProvider,
+    filter: Option<&Filter>,
+) -> Result<Vec<BTreeMap<String, Value>>, String> {
+    let schema = provider.schema();
+    let mut ids = BTreeSet::new();
+    provider
+        .scan(filter)
+        .into_iter()
+        .map(|mut row| {
+            let mut unique = |id| match ids.insert(id) {
+                true => Ok(()),
+                false => Err(format!("Row {} already exists", id)),
+            };
+            unique(row.id)
+                .and_then(|()| schema.apply(&mut row.columns))
+                .map_err(|e| format!("Table {} row {}: {}", table_name, row.id, e))?;
+            Ok(row_to_map(table_name, schema.key_column(), &row))
+        })
+        .collect()
+}
+
+/// Converts a table row to a `BTreeMap` of column names and values.
+///
+/// # Arguments
+///
+/// * `table_name` - The name of the table the row belongs to.
+/// * `key_column` - The name of the primary-key column of the table.
+/// * `row` - The row.
+///
+/// # Returns
+///
+/// The row, with column names qualified by the table name. Values the engine does not
+/// support are left out, so they read as `NULL`.
+pub(crate) fn row_to_map(table_name: &str, key_column: &str, row: &Row) -> BTreeMap<String, Value> {
+    let mut columns: BTreeMap<String, Value> = row
+        .columns
+        .iter()
+        .filter_map(|(k, v)| {
+            let v = Value::from_serde_value(v)?;
+            Some((format!("{}.{}", table_name, k), v))
+        })
+        .collect();
+    columns.insert(
+        format!("{table_name}.{key_column}"),
+        Value::Const(Const::Number(row.id)),
+    );
+
+    columns
+}
+
+impl fmt::Display for Const {
+    /// Formats a constant value as a string.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Const::Number(n) => write!(f, "{}", n),
+            Const::String(s) => write!(f, "{}", s),
+            Const::Null => write!(f, "NULL"),
         }
     }
 }
 
-impl Const {
-    /// Converts a constant value to a string.
-    ///
-    /// # Returns
-    ///
-    /// A string representation of the constant value.
-    fn to_string(&self) -> String {
+impl fmt::Display for Value {
+    /// Formats a `Value` as a string.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Const::Number(n) => n.to_string(),
-            Const::String(s) => s.clone(),
+            Value::Column(column) => write!(f, "{}", column.qualified_name()),
+            Value::Const(c) => write!(f, "{}", c),
+            Value::Param(number) => write!(f, "${}", number),
         }
     }
 }
 
-impl ToString for Value {
-    /// Converts a `Value` to a string.
+impl fmt::Display for Comparison {
+    /// Formats a comparison operator as it is written in SQL.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operator = match self {
+            Comparison::Eq => "=",
+            Comparison::Gt => ">",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Ge => ">=",
+            Comparison::Ne => "<>",
+        };
+        write!(f, "{}", operator)
+    }
+}
+
+impl Const {
+    /// Converts a constant to a serde JSON value.
     ///
     /// # Returns
     ///
-    /// A string representation of the value.
-    fn to_string(&self) -> String {
+    /// The corresponding serde JSON value.
+    pub(crate) fn to_serde_value(&self) -> serde_json::Value {
         match self {
-            Value::Column(Column {
-                table_name,
-                column_name,
-            }) => format!("{}.{}", table_name, column_name),
-            Value::Const(c) => c.to_string(),
+            Const::Number(n) => serde_json::Value::from(*n),
+            Const::String(s) => serde_json::Value::from(s.clone()),
+            Const::Null => serde_json::Value::Null,
         }
     }
 }
@@ -338,21 +907,21 @@ impl Value {
     /// # Returns
     ///
     /// An `Option` containing the constant value if it exists, otherwise `None`.
-    fn get_const(&self) -> Option<Const> {
+    pub(crate) fn get_const(&self) -> Option<Const> {
         match self {
             Value::Const(c) => Some(c.clone()),
             _ => None,
         }
     }
 
-    /// Gets the table name from a `Value`.
+    /// Gets the table name from a `Value`, qualified by its database if the column names one.
     ///
     /// # Returns
     ///
-    /// The table name as a string slice.
-    fn get_table_name(&self) -> &str {
+    /// The qualified table name.
+    pub(crate) fn get_table_name(&self) -> String {
         match self {
-            Value::Column(Column { table_name, .. }) => table_name,
+            Value::Column(column) => column.qualified_table(),
             _ => panic!("Expected a column value"),
         }
     }
@@ -362,7 +931,7 @@ impl Value {
     /// # Returns
     ///
     /// The column name as a string slice.
-    fn get_column_name(&self) -> &str {
+    pub(crate) fn get_column_name(&self) -> &str {
         match self {
             Value::Column(Column { column_name, .. }) => column_name,
             _ => panic!("Expected a column value"),
@@ -377,19 +946,23 @@ impl Value {
     ///
     /// # Returns
     ///
-    /// The corresponding `Value` object.
-    fn from_serde_value(value: &serde_json::Value) -> Self {
-        match value {
-            serde_json::Value::Number(n) => Value::Const(Const::Number(n.as_i64().unwrap())),
-            serde_json::Value::String(s) => Value::Const(Const::String(s.clone())),
-            _ => panic!("Unexpected value type"),
-        }
+    /// An `Option` containing the corresponding `Value`, or `None` for a value the engine does
+    /// not support, such as a real number or a boolean.
+    fn from_serde_value(value: &serde_json::Value) -> Option<Self> {
+        let constant = match value {
+            serde_json::Value::Number(n) => Const::Number(n.as_i64()?),
+            serde_json::Value::String(s) => Const::String(s.clone()),
+            serde_json::Value::Null => Const::Null,
+            _ => return None,
+        };
+        Some(Value::Const(constant))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{temporary_copy, temporary_path};
     use crate::{database, parser};
 
     #[test]
@@ -398,11 +971,27 @@ mod tests {
         let db = database::load_database(db_file_path).unwrap();
 
         let query = "SELECT movies.title FROM movies";
-        let parsed_query = parser::parse_query(&query);
+        let parsed_query = parser::parse_query(query).unwrap();
 
-        let view = View::execute(parsed_query, db);
+        let view = View::execute(parsed_query, &db).unwrap();
 
         assert_eq!(view.rows.len(), 3);
+
+        // Queries are checked as `execute_statement` checks them.
+        for query in [
+            "SELECT movies.title FROM nowhere",
+            "SELECT actors.name FROM movies",
+            "SELECT movies.title FROM movies WHERE actors.id = 1",
+            "SELECT movies.title FROM movies WHERE movies.id = $1",
+        ] {
+            let parsed_query = parser::parse_query(query).unwrap();
+            assert!(
+                View::explain(&parsed_query, &db, false).is_err(),
+                "{}",
+                query
+            );
+            assert!(View::execute(parsed_query, &db).is_err(), "{}", query);
+        }
     }
 
     #[test]
@@ -411,9 +1000,9 @@ mod tests {
         let db = database::load_database(db_file_path).unwrap();
 
         let query = "SELECT movies.title, actors.name FROM movies JOIN actors_in_movies ON actors_in_movies.movieID = movies.id JOIN actors ON actors_in_movies.actorID = actors.id";
-        let parsed_query = parser::parse_query(&query);
+        let parsed_query = parser::parse_query(query).unwrap();
 
-        let view = View::execute(parsed_query, db);
+        let view = View::execute(parsed_query, &db).unwrap();
 
         assert_eq!(view.rows.len(), 4);
     }
@@ -424,10 +1013,528 @@ mod tests {
         let db = database::load_database(db_file_path).unwrap();
 
         let query = "SELECT movies.title, actors.name FROM movies JOIN actors_in_movies ON actors_in_movies.movieID = movies.id JOIN actors ON actors_in_movies.actorID = actors.id WHERE movies.cert <= 15";
-        let parsed_query = parser::parse_query(&query);
+        let parsed_query = parser::parse_query(query).unwrap();
 
-        let view = View::execute(parsed_query, db);
+        let view = View::execute(parsed_query, &db).unwrap();
 
         assert_eq!(view.rows.len(), 3);
     }
+
+    #[test]
+    fn test_insert() {
+        let db_file_path = temporary_copy("database/test_data.json", "insert");
+        let mut db = database::load_database(&db_file_path).unwrap();
+
+        let statement = parser::parse_statement(
+            "INSERT INTO movies (id, title, cert) VALUES (4, 'Heat', 15), (5, 'Up', 12)",
+        )
+        .unwrap();
+        match execute_statement(statement, &mut db).unwrap() {
+            Outcome::Affected(count) => assert_eq!(count, 2),
+            outcome => panic!("Unexpected outcome {:?}", outcome),
+        }
+
+        let db = database::load_database(&db_file_path).unwrap();
+        let query =
+            parser::parse_query("SELECT movies.title FROM movies WHERE movies.cert = 15").unwrap();
+        let view = View::execute(query, &db).unwrap();
+        assert_eq!(view.rows.len(), 2);
+        assert_eq!(db.tables["movies"].get(5).unwrap().columns["title"], "Up");
+
+        let mut db = db;
+        let statement = parser::parse_statement(
+            "INSERT INTO movies (title, id) VALUES ('Aliens', NULL), ('Alien 3', 9), ('Heat 2', NULL)",
+        )
+        .unwrap();
+        execute_statement(statement, &mut db).unwrap();
+        let ids: Vec<i64> = db.tables["movies"].rows.iter().map(|row| row.id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4, 5, 6, 9, 10]);
+        assert_eq!(
+            db.tables["movies"].get(10).unwrap().columns["title"],
+            "Heat 2"
+        );
+
+        std::fs::remove_file(db_file_path).unwrap();
+    }
+
+    #[test]
+    fn test_failed_persist_leaves_tables_unchanged() {
+        let mut db = database::load_database("database/test_data.json").unwrap();
+        let path = temporary_path("missing_directory").join("database.json");
+        db.path = Some(path.to_str().unwrap().to_string());
+
+        for sql in [
+            "INSERT INTO movies (title) VALUES ('X')",
+            "UPDATE movies SET title = 'X' WHERE movies.id = 1",
+            "DELETE FROM movies",
+            "DROP TABLE movies",
+            "ALTER TABLE movies DROP COLUMN title",
+            "CREATE INDEX idx_title ON movies(title)",
+            "CREATE TABLE reviews (text TEXT)",
+        ] {
+            let statement = parser::parse_statement(sql).unwrap();
+            assert!(execute_statement(statement, &mut db).is_err(), "{}", sql);
+        }
+        assert!(!db.tables.contains_key("reviews"));
+        let movies = &db.tables["movies"];
+        assert_eq!(movies.rows.len(), 3);
+        assert_eq!(movies.get(1).unwrap().columns["title"], "The Matrix");
+        assert!(movies.indexes.is_empty());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_skipped_rows_are_kept() {
+        let path = temporary_path("skipped_rows.json");
+        let data = r#"{"movies": [{"id": 1, "title": "Heat"}, {"id": 1, "title": "Up"}]}"#;
+        std::fs::write(&path, data).unwrap();
+        let path = path.to_str().unwrap();
+        let mut db = database::load_database(path).unwrap();
+        assert_eq!(db.diagnostics.len(), 1);
+
+        for sql in [
+            "INSERT INTO movies (title) VALUES ('Up')",
+            "DELETE FROM movies",
+            "CREATE INDEX idx_title ON movies(title)",
+        ] {
+            let statement = parser::parse_statement(sql).unwrap();
+            assert!(execute_statement(statement, &mut db).is_err(), "{}", sql);
+        }
+        assert_eq!(db.tables["movies"].rows.len(), 1);
+        assert!(db.persist().is_err());
+        assert_eq!(std::fs::read_to_string(path).unwrap(), data);
+
+        let query = parser::parse_statement("SELECT movies.title FROM movies").unwrap();
+        assert!(execute_statement(query, &mut db).is_ok());
+
+        db.discard_skipped();
+        let statement = parser::parse_statement("DELETE FROM movies").unwrap();
+        execute_statement(statement, &mut db).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap(),
+            "{\n    \"movies\": []\n}"
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_statement_errors() {
+        let mut db = database::load_database("database/test_data.json").unwrap();
+        db.path = None;
+
+        for statement in [
+            "SELECT movies.title FROM missing",
+            "SELECT movies.title FROM movies JOIN missing ON missing.id = movies.id",
+            "SELECT actors.name FROM movies",
+            "SELECT movies.title FROM movies WHERE actors.id = 1",
+            "EXPLAIN SELECT movies.title FROM movies JOIN actors ON roles.actorID = actors.id",
+            "INSERT INTO missing (id) VALUES (1)",
+            "INSERT INTO movies (id, title) VALUES ('4', 'Heat')",
+            "INSERT INTO movies (id, title) VALUES (3, 'Heat')",
+            "INSERT INTO movies (id, title) VALUES (4, 'Heat'), (4, 'Up')",
+            "UPDATE missing SET title = 'Heat'",
+            "UPDATE movies SET id = 4 WHERE movies.id = 1",
+            "DELETE FROM missing",
+        ] {
+            let statement = parser::parse_statement(statement).unwrap();
+            assert!(execute_statement(statement, &mut db).is_err());
+        }
+        assert_eq!(db.tables["movies"].rows.len(), 3);
+    }
+
+    #[test]
+    fn test_update() {
+        let db_file_path = temporary_copy("database/test_data.json", "update");
+        let mut db = database::load_database(&db_file_path).unwrap();
+        db.create_index("idx_cert", "movies", "cert").unwrap();
+
+        let statement = parser::parse_statement(
+            "UPDATE movies SET cert = 12, title = 'Alien (1979)' WHERE movies.cert > 15",
+        )
+        .unwrap();
+        match execute_statement(statement, &mut db).unwrap() {
+            Outcome::Affected(count) => assert_eq!(count, 1),
+            outcome => panic!("Unexpected outcome {:?}", outcome),
+        }
+
+        let query =
+            parser::parse_query("SELECT movies.title FROM movies WHERE movies.cert = 12").unwrap();
+        let view = View::execute(query, &db).unwrap();
+        assert_eq!(view.rows.len(), 2);
+
+        let db = database::load_database(&db_file_path).unwrap();
+        assert_eq!(
+            db.tables["movies"].get(3).unwrap().columns["title"],
+            "Alien (1979)"
+        );
+        assert_eq!(db.tables["movies"].get(3).unwrap().columns["cert"], 12);
+
+        // The condition may qualify the table by its database, but only refer to its columns.
+        let mut db = db;
+        let statement =
+            parser::parse_statement("UPDATE movies SET cert = 18 WHERE main.movies.id = 3")
+                .unwrap();
+        assert!(matches!(
+            execute_statement(statement, &mut db),
+            Ok(Outcome::Affected(1))
+        ));
+        let statement =
+            parser::parse_statement("UPDATE movies SET cert = main.movies.id WHERE movies.id = 2")
+                .unwrap();
+        execute_statement(statement, &mut db).unwrap();
+        assert_eq!(db.tables["movies"].get(2).unwrap().columns["cert"], 2);
+        for (sql, error) in [
+            (
+                "UPDATE movies SET title = 'z' WHERE nowhere.id = 1",
+                "Column nowhere.id refers to a table the query does not read from",
+            ),
+            (
+                "UPDATE movies SET title = 'z' WHERE actors.id = 1",
+                "Column actors.id refers to a table the query does not read from",
+            ),
+            (
+                "UPDATE movies SET title = 'z' WHERE movies.nope = 1",
+                "Column movies.nope is not defined",
+            ),
+            (
+                "UPDATE movies SET title = movies.nope WHERE movies.id = 1",
+                "Column movies.nope is not defined",
+            ),
+            (
+                "UPDATE movies SET title = actors.name WHERE movies.id = 1",
+                "Column actors.name refers to a table the query does not read from",
+            ),
+            (
+                "UPDATE movies SET certt = 5 WHERE movies.id = 1",
+                "Column movies.certt is not defined",
+            ),
+            (
+                "UPDATE movies SET title = 'x', title = 'y' WHERE movies.id = 1",
+                "Column title is assigned more than once",
+            ),
+        ] {
+            let statement = parser::parse_statement(sql).unwrap();
+            assert_eq!(execute_statement(statement, &mut db).unwrap_err(), error);
+        }
+
+        std::fs::remove_file(db_file_path).unwrap();
+
+        // A column a row has no value for is read as NULL, and values the engine cannot query
+        // are kept.
+        let path = temporary_path("update_missing_value.json");
+        let data = r#"{"movies": [{"id": 1, "rating": 3, "score": 8.3}, {"id": 2, "rating": 4, "cert": 12}]}"#;
+        std::fs::write(&path, data).unwrap();
+        let mut db = database::load_database(path.to_str().unwrap()).unwrap();
+        let statement = parser::parse_statement("UPDATE movies SET rating = movies.cert").unwrap();
+        assert!(matches!(
+            execute_statement(statement, &mut db),
+            Ok(Outcome::Affected(2))
+        ));
+        assert!(db.tables["movies"].get(1).unwrap().columns["rating"].is_null());
+        assert_eq!(db.tables["movies"].get(2).unwrap().columns["rating"], 12);
+        assert_eq!(db.tables["movies"].get(1).unwrap().columns["score"], 8.3);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_delete() {
+        let db_file_path = temporary_copy("database/test_data.json", "delete");
+        let mut db = database::load_database(&db_file_path).unwrap();
+        db.create_index("idx_movie", "actors_in_movies", "movieID")
+            .unwrap();
+
+        let statement = parser::parse_statement(
+            "DELETE FROM actors_in_movies WHERE actors_in_movies.movieID = 1",
+        )
+        .unwrap();
+        match execute_statement(statement, &mut db).unwrap() {
+            Outcome::Affected(count) => assert_eq!(count, 2),
+            outcome => panic!("Unexpected outcome {:?}", outcome),
+        }
+
+        let query = "SELECT movies.title, actors_in_movies.actorID FROM movies JOIN actors_in_movies ON actors_in_movies.movieID = movies.id";
+        let view = View::execute(parser::parse_query(query).unwrap(), &db).unwrap();
+        assert_eq!(view.rows.len(), 2);
+
+        let mut db = database::load_database(&db_file_path).unwrap();
+        assert_eq!(db.tables["actors_in_movies"].rows.len(), 2);
+
+        for (sql, error) in [
+            (
+                "DELETE FROM movies WHERE actors.id = 1",
+                "Column actors.id refers to a table the query does not read from",
+            ),
+            (
+                "DELETE FROM movies WHERE 1 = movies.nope",
+                "Column movies.nope is not defined",
+            ),
+        ] {
+            let statement = parser::parse_statement(sql).unwrap();
+            assert_eq!(execute_statement(statement, &mut db).unwrap_err(), error);
+        }
+        assert_eq!(db.tables["movies"].rows.len(), 3);
+
+        std::fs::remove_file(db_file_path).unwrap();
+    }
+
+    #[test]
+    fn test_primary_key() {
+        let db_file_path = temporary_copy("database/test_data.json", "engine_primary_key");
+        let mut db = database::load_database(&db_file_path).unwrap();
+
+        for statement in parser::parse_statements(
+            "CREATE TABLE reviews (reviewID INTEGER PRIMARY KEY, id TEXT, movieID INTEGER);
+             INSERT INTO reviews (id, movieID) VALUES ('r1', 1), ('r2', 3);
+             UPDATE reviews SET id = 'r3' WHERE reviews.reviewID = 2",
+        )
+        .unwrap()
+        {
+            execute_statement(statement, &mut db).unwrap();
+        }
+
+        let db = database::load_database(&db_file_path).unwrap();
+        let query = "SELECT movies.title, reviews.id FROM reviews JOIN movies ON movies.id = reviews.movieID WHERE reviews.reviewID >= 2";
+        let view = View::execute(parser::parse_query(query).unwrap(), &db).unwrap();
+        assert_eq!(view.rows.len(), 1);
+        assert_eq!(view.rows[0]["reviews.id"].to_string(), "r3");
+        assert_eq!(view.rows[0]["movies.title"].to_string(), "Alien");
+
+        let mut db = db;
+        let statement =
+            parser::parse_statement("UPDATE reviews SET reviewID = 3 WHERE reviews.reviewID = 1")
+                .unwrap();
+        assert!(execute_statement(statement, &mut db).is_err());
+
+        std::fs::remove_file(db_file_path).unwrap();
+    }
+
+    #[test]
+    fn test_drop_and_alter_table() {
+        let db_file_path = temporary_copy("database/test_data.json", "alter_table");
+        let mut db = database::load_database(&db_file_path).unwrap();
+        db.create_index("idx_cert", "movies", "cert").unwrap();
+
+        for statement in parser::parse_statements(
+            "DROP TABLE actors;
+             ALTER TABLE movies ADD COLUMN rating INTEGER NOT NULL DEFAULT 0;
+             ALTER TABLE movies RENAME COLUMN cert TO certificate;
+             ALTER TABLE movies DROP COLUMN title;
+             CREATE TABLE reviews (reviewID INTEGER PRIMARY KEY, text TEXT);
+             ALTER TABLE reviews RENAME COLUMN reviewID TO id;
+             ALTER TABLE reviews RENAME COLUMN text TO body",
+        )
+        .unwrap()
+        {
+            execute_statement(statement, &mut db).unwrap();
+        }
+        assert_eq!(
+            db.tables["movies"].index_on("certificate"),
+            Some("idx_cert")
+        );
+
+        let db = database::load_database(&db_file_path).unwrap();
+        assert!(!db.tables.contains_key("actors"));
+        let movie = db.tables["movies"].get(1).unwrap();
+        assert_eq!(
+            movie.columns.keys().collect::<Vec<_>>(),
+            vec!["certificate", "rating"]
+        );
+        assert_eq!(movie.columns["rating"], 0);
+        let reviews = &db.tables["reviews"];
+        assert_eq!(reviews.key_column(), "id");
+        assert_eq!(reviews.schema.as_ref().unwrap().columns[0].name, "body");
+
+        let mut db = db;
+        for statement in [
+            "DROP TABLE actors",
+            "ALTER TABLE actors DROP COLUMN name",
+            "ALTER TABLE movies ADD COLUMN rating INTEGER",
+            "ALTER TABLE movies ADD COLUMN year INTEGER NOT NULL",
+            "ALTER TABLE movies ADD COLUMN year INTEGER DEFAULT 'new'",
+            "ALTER TABLE movies ADD COLUMN key INTEGER PRIMARY KEY",
+            "ALTER TABLE movies DROP COLUMN id",
+            "ALTER TABLE movies DROP COLUMN title",
+            "ALTER TABLE actors_in_movies RENAME COLUMN id TO pairID",
+            "ALTER TABLE movies RENAME COLUMN rating TO certificate",
+            "ALTER TABLE reviews RENAME COLUMN body TO id",
+            "CREATE INDEX idx_nope ON reviews(nope)",
+        ] {
+            let parsed = parser::parse_statement(statement).unwrap();
+            assert!(execute_statement(parsed, &mut db).is_err(), "{}", statement);
+        }
+
+        // Indexes follow the values that ALTER TABLE gives, moves or removes.
+        for statement in parser::parse_statements(
+            "CREATE INDEX idx_r ON movies(r);
+             ALTER TABLE movies ADD COLUMN r INTEGER DEFAULT 1;
+             CREATE INDEX idx_year ON movies(year);
+             ALTER TABLE movies RENAME COLUMN r TO year",
+        )
+        .unwrap()
+        {
+            execute_statement(statement, &mut db).unwrap();
+        }
+        let query =
+            parser::parse_query("SELECT movies.id FROM movies WHERE movies.year = 1").unwrap();
+        let plan = View::explain(&query, &db, false).unwrap();
+        assert!(plan.contains("Index Scan"), "{}", plan);
+        assert_eq!(View::execute(query, &db).unwrap().rows.len(), 3);
+
+        // A column added to a table without a schema is kept in an open schema and enforced.
+        let statement =
+            parser::parse_statement("ALTER TABLE actors_in_movies ADD COLUMN role TEXT").unwrap();
+        execute_statement(statement, &mut db).unwrap();
+        let mut db = database::load_database(&db_file_path).unwrap();
+        let schema = db.tables["actors_in_movies"].schema.clone().unwrap();
+        assert!(schema.open);
+        assert_eq!(schema.columns[0].name, "role");
+        assert_eq!(db.schema("actors_in_movies").unwrap().columns.len(), 3);
+        for (statement, succeeds) in [
+            (
+                "INSERT INTO actors_in_movies (actorID, role) VALUES (1, 7)",
+                false,
+            ),
+            (
+                "INSERT INTO actors_in_movies (actorID, role) VALUES (1, 'Neo')",
+                true,
+            ),
+            ("ALTER TABLE actors_in_movies DROP COLUMN role", true),
+        ] {
+            let parsed = parser::parse_statement(statement).unwrap();
+            assert_eq!(
+                execute_statement(parsed, &mut db).is_ok(),
+                succeeds,
+                "{}",
+                statement
+            );
+        }
+        assert_eq!(db.tables["actors_in_movies"].schema, None);
+
+        std::fs::remove_file(db_file_path).unwrap();
+    }
+
+    #[test]
+    fn test_directory() {
+        let dir = temporary_path("engine_directory");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("albums.json"),
+            r#"[{"id": 1, "title": "Abbey Road"}, {"id": 2, "title": "Thriller"}]"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("plays.csv"), "albumID,user\n1,ann\n2,bob\n1,cid\n").unwrap();
+
+        let mut db = database::load_database(dir.to_str().unwrap()).unwrap();
+        let query = "SELECT albums.title, plays.user FROM plays JOIN albums ON albums.id = plays.albumID WHERE albums.title = 'Abbey Road'";
+        let view = View::execute(parser::parse_query(query).unwrap(), &db).unwrap();
+        assert_eq!(view.rows.len(), 2);
+
+        let statement = parser::parse_statement("DELETE FROM plays").unwrap();
+        assert!(execute_statement(statement, &mut db).is_err());
+        assert_eq!(db.tables["plays"].rows.len(), 3);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_attach() {
+        let mut db = database::load_database("database/test_data.json").unwrap();
+        db.path = None;
+        let statement =
+            parser::parse_statement("ATTACH 'database/music_data.json' AS music").unwrap();
+        execute_statement(statement, &mut db).unwrap();
+
+        let query = "SELECT music.albums.title, movies.title FROM music.albums JOIN movies ON movies.id = music.albums.id WHERE music.albums.rating > 5";
+        let view = View::execute(parser::parse_query(query).unwrap(), &db).unwrap();
+        let titles: Vec<(String, String)> = view
+            .rows
+            .iter()
+            .map(|row| {
+                (
+                    row["music.albums.title"].to_string(),
+                    row["movies.title"].to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                ("Thriller".to_string(), "Titanic".to_string()),
+                ("Back in Black".to_string(), "Alien".to_string())
+            ]
+        );
+
+        // Unqualified names are found in the attached databases, and `main` names the database itself.
+        let query = "SELECT artists.name, main.movies.title FROM artists JOIN main.movies ON main.movies.id = artists.id";
+        let view = View::execute(parser::parse_query(query).unwrap(), &db).unwrap();
+        assert_eq!(view.rows.len(), 3);
+
+        // Columns may name their table differently from the clause that reads it.
+        let query = "SELECT movies.title, albums.title FROM main.movies JOIN albums ON music.albums.id = main.movies.id WHERE main.movies.id = 1";
+        let statement = parser::parse_statement(query).unwrap();
+        let Outcome::Rows(view) = execute_statement(statement, &mut db).unwrap() else {
+            panic!("Expected rows");
+        };
+        let names: Vec<String> = view.columns.iter().map(Column::qualified_name).collect();
+        assert_eq!(names, ["main.movies.title", "albums.title"]);
+        assert_eq!(view.rows[0]["main.movies.title"].to_string(), "The Matrix");
+        assert_eq!(view.rows[0]["albums.title"].to_string(), "Abbey Road");
+
+        let statement = parser::parse_statement("SELECT music.movies.title FROM movies").unwrap();
+        assert!(execute_statement(statement, &mut db).is_err());
+
+        for statement in [
+            "ATTACH 'database/music_data.json' AS music",
+            "ATTACH 'database/music_data.json' AS main",
+            "ATTACH 'database/missing.json' AS missing",
+        ] {
+            let parsed = parser::parse_statement(statement).unwrap();
+            assert!(execute_statement(parsed, &mut db).is_err(), "{}", statement);
+        }
+    }
+
+    #[test]
+    fn test_create_table() {
+        let db_file_path = temporary_copy("database/test_data.json", "create_table");
+        let mut db = database::load_database(&db_file_path).unwrap();
+
+        for statement in parser::parse_statements(
+            "CREATE TABLE reviews (id INTEGER, movieID INTEGER NOT NULL, text TEXT, stars INTEGER DEFAULT 3);
+             INSERT INTO reviews (id, movieID, text) VALUES (1, 1, 'Great'), (2, 3, NULL)",
+        )
+        .unwrap()
+        {
+            execute_statement(statement, &mut db).unwrap();
+        }
+
+        let db = database::load_database(&db_file_path).unwrap();
+        let reviews = &db.tables["reviews"];
+        assert_eq!(reviews.schema.as_ref().unwrap().columns.len(), 3);
+        assert_eq!(reviews.get(1).unwrap().columns["stars"], 3);
+        assert!(reviews.get(2).unwrap().columns["text"].is_null());
+
+        let query = "SELECT movies.title, reviews.text FROM movies JOIN reviews ON reviews.movieID = movies.id WHERE reviews.stars = 3";
+        let view = View::execute(parser::parse_query(query).unwrap(), &db).unwrap();
+        assert_eq!(view.rows.len(), 2);
+
+        let mut db = db;
+        for statement in [
+            "CREATE TABLE movies (title TEXT)",
+            "CREATE TABLE reviews2 (stars INTEGER DEFAULT 'three')",
+            "CREATE TABLE reviews2 (stars INTEGER, stars TEXT)",
+            "CREATE TABLE reviews2 (id TEXT)",
+            "CREATE TABLE reviews2 (a INTEGER PRIMARY KEY, b INTEGER PRIMARY KEY)",
+            "CREATE TABLE reviews2 (a TEXT PRIMARY KEY)",
+            "INSERT INTO reviews (id, text) VALUES (3, 'Missing movie')",
+            "INSERT INTO reviews (id, movieID) VALUES (3, '1')",
+            "INSERT INTO reviews (id, movieID, year) VALUES (3, 1, 1999)",
+            "UPDATE reviews SET movieID = NULL",
+            "UPDATE reviews SET stars = 'five'",
+        ] {
+            let statement = parser::parse_statement(statement).unwrap();
+            assert!(execute_statement(statement, &mut db).is_err());
+        }
+
+        std::fs::remove_file(db_file_path).unwrap();
+    }
 }
@@ -0,0 +1,278 @@
+use std::fmt;
+
+use crate::parser::{
+    AlterAction, Column, ColumnDefinition, Const, Query, Statement, Value, ValueTest,
+};
+
+/// The indentation of a line continuing the clause above it, such as a `JOIN` under `FROM`.
+const INDENT: &str = "  ";
+
+/// Formats a query as canonical SQL.
+///
+/// Keywords are upper case, each clause starts a line and `JOIN` clauses are indented under
+/// `FROM`. Parsing the result with `parser::parse_query` gives a query equal to `query`.
+///
+/// # Arguments
+///
+/// * `query` - The query to format.
+///
+/// # Returns
+///
+/// The SQL text of the query, without a terminating `;`.
+pub fn format_query(query: &Query) -> String {
+    let columns: Vec<String> = query.select.iter().map(format_column).collect();
+    let mut lines = vec![
+        format!("SELECT {}", columns.join(", ")),
+        format!("FROM {}", query.qualified_from()),
+    ];
+    for join in &query.joins {
+        lines.push(format!(
+            "{}JOIN {} ON {}",
+            INDENT,
+            join.qualified_table(),
+            format_test(&join.on)
+        ));
+    }
+    if let Some(where_clause) = &query.where_clause {
+        lines.push(format!("WHERE {}", format_test(where_clause)));
+    }
+    if !query.order_by.is_empty() {
+        let columns: Vec<String> = query
+            .order_by
+            .iter()
+            .map(|o| {
+                let direction = if o.descending { " DESC" } else { "" };
+                format!("{}{}", format_column(&o.column), direction)
+            })
+            .collect();
+        lines.push(format!("ORDER BY {}", columns.join(", ")));
+    }
+    if let Some(limit) = query.limit {
+        lines.push(format!("LIMIT {}", limit));
+    }
+    lines.join("\n")
+}
+
+/// Formats a statement as canonical SQL, in the layout `format_query` gives queries.
+///
+/// Parsing the result with `parser::parse_statement` gives a statement equal to `statement`.
+///
+/// # Arguments
+///
+/// * `statement` - The statement to format.
+///
+/// # Returns
+///
+/// The SQL text of the statement, without a terminating `;`.
+pub fn format_statement(statement: &Statement) -> String {
+    match statement {
+        Statement::Select(query) => format_query(query),
+        Statement::Explain { query, analyze } => {
+            let prefix = if *analyze {
+                "EXPLAIN ANALYZE"
+            } else {
+                "EXPLAIN"
+            };
+            format!("{} {}", prefix, format_query(query))
+        }
+        Statement::CreateIndex(create) => format!(
+            "CREATE INDEX {} ON {}({})",
+            create.index_name, create.table_name, create.column_name
+        ),
+        Statement::CreateTable(create) => {
+            let columns: Vec<String> = create
+                .columns
+                .iter()
+                .map(|column| format!("{}{}", INDENT, format_column_definition(column)))
+                .collect();
+            format!(
+                "CREATE TABLE {} (\n{}\n)",
+                create.table_name,
+                columns.join(",\n")
+            )
+        }
+        Statement::Insert(insert) => {
+            let rows: Vec<String> = insert
+                .rows
+                .iter()
+                .map(|row| {
+                    let values: Vec<String> = row.iter().map(format_value).collect();
+                    format!("{}({})", INDENT, values.join(", "))
+                })
+                .collect();
+            format!(
+                "INSERT INTO {} ({})\nVALUES\n{}",
+                insert.table_name,
+                insert.columns.join(", "),
+                rows.join(",\n")
+            )
+        }
+        Statement::Update(update) => {
+            let assignments: Vec<String> = update
+                .assignments
+                .iter()
+                .map(|(column_name, value)| format!("{} = {}", column_name, format_value(value)))
+                .collect();
+            let mut sql = format!(
+                "UPDATE {}\nSET {}",
+                update.table_name,
+                assignments.join(", ")
+            );
+            if let Some(where_clause) = &update.where_clause {
+                sql.push_str(&format!("\nWHERE {}", format_test(where_clause)));
+            }
+            sql
+        }
+        Statement::Delete(delete) => {
+            let mut sql = format!("DELETE FROM {}", delete.table_name);
+            if let Some(where_clause) = &delete.where_clause {
+                sql.push_str(&format!("\nWHERE {}", format_test(where_clause)));
+            }
+            sql
+        }
+        Statement::DropTable(drop) => format!("DROP TABLE {}", drop.table_name),
+        Statement::AlterTable(alter) => {
+            let action = match &alter.action {
+                AlterAction::Add(column) => {
+                    format!("ADD COLUMN {}", format_column_definition(column))
+                }
+                AlterAction::Drop(column_name) => format!("DROP COLUMN {}", column_name),
+                AlterAction::Rename { from, to } => format!("RENAME COLUMN {} TO {}", from, to),
+            };
+            format!("ALTER TABLE {} {}", alter.table_name, action)
+        }
+        Statement::Attach(attach) => format!(
+            "ATTACH {} AS {}",
+            format_const(&Const::String(attach.path.clone())),
+            attach.database_name
+        ),
+    }
+}
+
+/// Formats a constant as an SQL literal, quoting strings and doubling the quotes inside them.
+pub fn format_const(constant: &Const) -> String {
+    match constant {
+        Const::Number(n) => n.to_string(),
+        Const::String(s) => format!("'{}'", s.replace('\'', "''")),
+        Const::Null => "NULL".to_string(),
+    }
+}
+
+/// Formats a column qualified by its table.
+fn format_column(column: &Column) -> String {
+    column.qualified_name()
+}
+
+/// Formats a value, writing parameters as `$number`.
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Column(column) => format_column(column),
+        Value::Const(constant) => format_const(constant),
+        Value::Param(number) => format!("${}", number),
+    }
+}
+
+/// Formats a value test, such as the condition of a `WHERE` clause.
+fn format_test(test: &ValueTest) -> String {
+    format!(
+        "{} {} {}",
+        format_value(&test.left),
+        test.comparison,
+        format_value(&test.right)
+    )
+}
+
+/// Formats a column definition of `CREATE TABLE` or `ALTER TABLE ... ADD COLUMN`.
+fn format_column_definition(column: &ColumnDefinition) -> String {
+    let mut definition = format!("{} {}", column.name, column.data_type);
+    if column.primary_key {
+        definition.push_str(" PRIMARY KEY");
+    }
+    if column.not_null {
+        definition.push_str(" NOT NULL");
+    }
+    if let Some(default) = &column.default {
+        definition.push_str(&format!(" DEFAULT {}", format_const(default)));
+    }
+    definition
+}
+
+impl fmt::Display for Query {
+    /// Formats the query as canonical SQL, as `format_query` does.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format_query(self))
+    }
+}
+
+impl fmt::Display for Statement {
+    /// Formats the statement as canonical SQL, as `format_statement` does.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format_statement(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_query, parse_statement};
+
+    #[test]
+    fn test_format_query() {
+        let query = parse_query(
+            "SELECT movies.title,actors.name FROM movies JOIN actors_in_movies ON actors_in_movies.movieID = movies.id\n\
+             JOIN actors ON actors.id = actors_in_movies.actorID WHERE movies.title   <> 'It''s' ORDER BY movies.cert DESC, actors.name ASC LIMIT 5",
+        ).unwrap();
+        let sql = format_query(&query);
+        assert_eq!(
+            sql,
+            "SELECT movies.title, actors.name\n\
+             FROM movies\n  \
+             JOIN actors_in_movies ON actors_in_movies.movieID = movies.id\n  \
+             JOIN actors ON actors.id = actors_in_movies.actorID\n\
+             WHERE movies.title <> 'It''s'\n\
+             ORDER BY movies.cert DESC, actors.name\n\
+             LIMIT 5"
+        );
+        assert_eq!(parse_query(&sql).unwrap(), query);
+        assert_eq!(query.to_string(), sql);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for sql in [
+            "SELECT music.albums.title FROM music.albums WHERE ? < music.albums.rating",
+            "EXPLAIN SELECT movies.title FROM movies WHERE movies.cert = NULL",
+            "EXPLAIN ANALYZE SELECT movies.title FROM movies WHERE -5 >= movies.id LIMIT 0",
+            "CREATE INDEX idx_cert ON movies(cert)",
+            "CREATE TABLE movies (id INTEGER PRIMARY KEY, title TEXT NOT NULL DEFAULT 'Un''titled', cert INTEGER DEFAULT NULL)",
+            "INSERT INTO movies (id, title, cert) VALUES (4, 'Schindler''s List', 15), ($2, $1, NULL)",
+            "UPDATE movies SET title = 'Up', cert = movies.id WHERE movies.id = ?",
+            "UPDATE movies SET cert = -1",
+            "DELETE FROM movies WHERE movies.title = 'Alien'",
+            "DELETE FROM movies",
+            "DROP TABLE movies",
+            "ALTER TABLE movies ADD COLUMN year INTEGER NOT NULL DEFAULT 1999",
+            "ALTER TABLE movies DROP COLUMN cert",
+            "ALTER TABLE movies RENAME COLUMN cert TO rating",
+            "ATTACH 'database/it''s.json' AS music",
+        ] {
+            let statement = parse_statement(sql).unwrap();
+            let formatted = format_statement(&statement);
+            assert_eq!(
+                parse_statement(&formatted).unwrap(),
+                statement,
+                "{}",
+                formatted
+            );
+            assert_eq!(format_statement(&parse_statement(&formatted).unwrap()), formatted);
+        }
+
+        assert_eq!(
+            format_statement(
+                &parse_statement("INSERT INTO movies (id, title) VALUES (1, 'Heat'), (2, ?)")
+                    .unwrap()
+            ),
+            "INSERT INTO movies (id, title)\nVALUES\n  (1, 'Heat'),\n  (2, $1)"
+        );
+    }
+}
@@ -0,0 +1,41 @@
+//! An SQL engine that runs a simplified form of SQL against a JSON database.
+//!
+//! A [`Connection`] opens a database, prepares statements and runs them, and returns the rows
+//! of a query with the columns of the result:
+//!
+//! ```
+//! use simple_sql_engine::Connection;
+//!
+//! let mut connection = Connection::open("database/movie_data.json")?;
+//! let statement = connection.prepare("SELECT movies.title, movies.cert FROM movies")?;
+//! let rows = statement.query(&mut connection)?;
+//!
+//! assert_eq!(rows.columns()[0].name, "movies.title");
+//! for row in rows.iter() {
+//!     let title: String = row.get(0)?;
+//!     let cert: Option<i64> = row.get_by_name("cert")?;
+//!     println!("{} ({:?})", title, cert);
+//! }
+//! # Ok::<(), simple_sql_engine::Error>(())
+//! ```
+//!
+//! The modules below give access to the parts of the engine: the parser, the database and its
+//! loading, table providers, a builder of queries, their formatting as SQL, the execution of
+//! parsed statements and the output formats of query results.
+
+pub mod builder;
+pub mod connection;
+pub mod csv;
+pub mod database;
+pub mod engine;
+pub mod formatter;
+pub mod output;
+pub mod parser;
+mod plan;
+pub mod provider;
+#[cfg(test)]
+mod test_util;
+
+pub use connection::{
+    Connection, Error, FromConst, PreparedStatement, ResultColumn, ResultRow, Rows,
+};
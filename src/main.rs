@@ -1,17 +1,327 @@
 use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::process::ExitCode;
 
-mod database;
-mod engine;
-mod parser;
+use simple_sql_engine::database::LoadMode;
+use simple_sql_engine::engine::Outcome;
+use simple_sql_engine::output::{self, Format};
+use simple_sql_engine::Connection;
 
-fn main() {
-    let database_file_path = "database/movie_data.json";
-    let db = database::load_database(database_file_path).unwrap();
+mod repl;
 
-    let query_file_path = "query";
-    let sql_query = fs::read_to_string(query_file_path).unwrap();
-    let parsed_query = parser::parse_query(&sql_query);
+const USAGE: &str = "\
+Usage: simple_sql_engine [OPTIONS] [SQL]
 
-    let v = engine::View::execute(parsed_query, db);
-    v.display();
+Runs SQL statements against a database. The statements are taken from SQL, from the file
+given with --file, from standard input if it is not a terminal, or else from the file `query`.
+With --interactive, they are typed into a shell that keeps the database loaded.
+
+Options:
+  -d, --database <PATH>  The database file or directory [default: database/movie_data.json]
+  -f, --file <PATH>      Read the statements from a file, or from standard input if PATH is -
+  -o, --format <FORMAT>  The output format of query results: table, box, csv, tsv, json, ndjson,
+                         markdown or html [default: table]
+      --strict           Fail if the database has malformed tables or rows
+      --discard-malformed
+                         Allow changing a database whose malformed tables or rows were
+                         skipped, which drops them from its file
+  -i, --interactive      Start an interactive shell
+  -h, --help             Print this help
+";
+
+/// Represents where the statements to run are read from.
+#[derive(Debug, PartialEq)]
+enum Source {
+    /// Statements given on the command line.
+    Inline(String),
+    /// A file holding the statements.
+    File(String),
+    /// Standard input.
+    Stdin,
+    /// Standard input if it is not a terminal, or else the file `query`.
+    Default,
+}
+
+/// Represents the options given on the command line.
+#[derive(Debug, PartialEq)]
+struct Options {
+    database: String,
+    source: Source,
+    format: Format,
+    strict: bool,
+    discard_malformed: bool,
+    interactive: bool,
+    help: bool,
+}
+
+/// Parses the command-line arguments.
+///
+/// # Arguments
+///
+/// * `args` - The arguments, without the name of the program.
+///
+/// # Returns
+///
+/// A `Result` containing the options, or a message describing why the arguments are invalid.
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options {
+        database: "database/movie_data.json".to_string(),
+        source: Source::Default,
+        format: Format::Table,
+        strict: false,
+        discard_malformed: false,
+        interactive: false,
+        help: false,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("Option {} needs a value", name))
+        };
+        let source = match arg.as_str() {
+            "-d" | "--database" => {
+                options.database = value(&arg)?;
+                continue;
+            }
+            "-f" | "--file" => match value(&arg)?.as_str() {
+                "-" => Source::Stdin,
+                path => Source::File(path.to_string()),
+            },
+            "-o" | "--format" => {
+                options.format = value(&arg)?.parse()?;
+                continue;
+            }
+            "--strict" => {
+                options.strict = true;
+                continue;
+            }
+            "--discard-malformed" => {
+                options.discard_malformed = true;
+                continue;
+            }
+            "-i" | "--interactive" => {
+                options.interactive = true;
+                continue;
+            }
+            "-h" | "--help" => {
+                options.help = true;
+                continue;
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                return Err(format!("Unknown option {}", arg));
+            }
+            _ => Source::Inline(arg),
+        };
+
+        if options.source != Source::Default {
+            return Err("Give the statements only once".to_string());
+        }
+        options.source = source;
+    }
+
+    if options.interactive && options.source != Source::Default {
+        return Err("Give either the statements or --interactive".to_string());
+    }
+    Ok(options)
+}
+
+/// Reads the statements to run.
+///
+/// # Arguments
+///
+/// * `source` - Where the statements are read from.
+///
+/// # Returns
+///
+/// A `Result` containing the statements, or a message describing why they could not be read.
+fn read_statements(source: Source) -> Result<String, String> {
+    let read_stdin = || {
+        let mut sql = String::new();
+        io::stdin()
+            .read_to_string(&mut sql)
+            .map_err(|e| format!("Cannot read standard input: {}", e))?;
+        Ok(sql)
+    };
+    let read_file =
+        |path: &str| fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e));
+
+    match source {
+        Source::Inline(sql) => Ok(sql),
+        Source::File(path) => read_file(&path),
+        Source::Stdin => read_stdin(),
+        Source::Default if io::stdin().is_terminal() => read_file("query"),
+        Source::Default => read_stdin(),
+    }
+}
+
+/// Loads a database, reporting the tables and rows skipped and the schema warnings on
+/// standard error.
+///
+/// # Arguments
+///
+/// * `path` - The path of the database file or directory.
+/// * `strict` - Whether malformed tables or rows fail the load instead of being skipped.
+/// * `discard_malformed` - Whether statements may change the database when malformed tables
+///   or rows were skipped, dropping them from its file.
+///
+/// # Returns
+///
+/// A `Result` containing a connection to the database, or a message describing why it could
+/// not be loaded.
+fn open_database(path: &str, strict: bool, discard_malformed: bool) -> Result<Connection, String> {
+    let mode = if strict {
+        LoadMode::Strict
+    } else {
+        LoadMode::Lenient
+    };
+    let mut connection = Connection::open_with_mode(path, mode)
+        .map_err(|e| format!("Cannot load {}: {}", path, e))?;
+    for diagnostic in &connection.database().diagnostics {
+        eprintln!("warning: skipped {}", diagnostic);
+    }
+    for warning in &connection.database().warnings {
+        eprintln!("warning: {}", warning);
+    }
+    if discard_malformed {
+        connection.discard_skipped();
+    }
+    Ok(connection)
+}
+
+/// Prints the outcome of a statement.
+///
+/// # Arguments
+///
+/// * `outcome` - The outcome of the statement.
+/// * `format` - How query results are printed.
+/// * `out` - The writer to print to.
+///
+/// # Returns
+///
+/// A `Result` indicating success or an I/O error.
+fn print_outcome<W: Write>(outcome: Outcome, format: Format, out: &mut W) -> io::Result<()> {
+    match outcome {
+        Outcome::Rows(v) => output::write_view(&v, format, out),
+        Outcome::Plan(plan) => write!(out, "{}", plan),
+        Outcome::Done => Ok(()),
+        Outcome::Affected(count) => writeln!(out, "{} row(s) affected", count),
+    }
+}
+
+/// Loads the database and runs the statements, printing their results.
+///
+/// # Arguments
+///
+/// * `options` - The options given on the command line.
+///
+/// # Returns
+///
+/// A `Result` indicating success, or a message describing the first error.
+fn run(options: Options) -> Result<(), String> {
+    let mut connection =
+        open_database(&options.database, options.strict, options.discard_malformed)?;
+
+    if options.interactive {
+        let mut repl = repl::Repl::new(
+            connection,
+            options.strict,
+            options.discard_malformed,
+            options.format,
+        );
+        let prompt = io::stdin().is_terminal();
+        return repl
+            .run(io::stdin().lock(), &mut io::stdout().lock(), prompt)
+            .map_err(|e| e.to_string());
+    }
+
+    let sql = read_statements(options.source)?;
+    let statements = connection.prepare_all(&sql).map_err(|e| e.to_string())?;
+
+    let mut stdout = io::stdout().lock();
+    for statement in statements {
+        let outcome = statement
+            .execute(&mut connection)
+            .map_err(|e| e.to_string())?;
+        print_outcome(outcome, options.format, &mut stdout).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    if options.help {
+        print!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match run(options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse(&[]).unwrap();
+        assert_eq!(options.database, "database/movie_data.json");
+        assert_eq!(options.source, Source::Default);
+        assert_eq!(options.format, Format::Table);
+        assert!(!options.strict);
+        assert!(!options.discard_malformed);
+        assert!(!options.interactive);
+
+        let options = parse(&[
+            "--database",
+            "database/music_data.json",
+            "-o",
+            "csv",
+            "--strict",
+            "SELECT albums.title FROM albums",
+        ])
+        .unwrap();
+        assert_eq!(options.database, "database/music_data.json");
+        assert_eq!(
+            options.source,
+            Source::Inline("SELECT albums.title FROM albums".to_string())
+        );
+        assert_eq!(options.format, Format::Csv);
+        assert!(options.strict);
+
+        assert_eq!(parse(&["-f", "-"]).unwrap().source, Source::Stdin);
+        assert_eq!(
+            parse(&["--file", "query"]).unwrap().source,
+            Source::File("query".to_string())
+        );
+        assert!(parse(&["-h"]).unwrap().help);
+        assert!(parse(&["--interactive"]).unwrap().interactive);
+        assert!(parse(&["--discard-malformed"]).unwrap().discard_malformed);
+
+        for args in [
+            &["--database"][..],
+            &["-o", "xml"],
+            &["--verbose"],
+            &["-f", "query", "SELECT movies.title FROM movies"],
+            &["-i", "SELECT movies.title FROM movies"],
+        ] {
+            assert!(parse(args).is_err(), "{:?}", args);
+        }
+    }
 }
@@ -0,0 +1,615 @@
+use std::cmp::max;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::csv;
+use crate::engine::View;
+use crate::parser::{Const, Value};
+
+/// Represents how the rows of a query result are written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// A grid of columns separated by `|`.
+    Table,
+    /// A grid drawn with box-drawing characters.
+    Box,
+    /// Comma-separated values, as written by `csv::write_view`.
+    Csv,
+    /// Tab-separated values.
+    Tsv,
+    /// A JSON array with an object for each row.
+    Json,
+    /// A JSON object for each row, one per line.
+    Ndjson,
+    /// A Markdown table.
+    Markdown,
+    /// An HTML table.
+    Html,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Format::Table),
+            "box" => Ok(Format::Box),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::Ndjson),
+            "markdown" => Ok(Format::Markdown),
+            "html" => Ok(Format::Html),
+            _ => Err(format!("Unknown output format {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    /// Formats the format as it is named on the command line.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Format::Table => write!(f, "table"),
+            Format::Box => write!(f, "box"),
+            Format::Csv => write!(f, "csv"),
+            Format::Tsv => write!(f, "tsv"),
+            Format::Json => write!(f, "json"),
+            Format::Ndjson => write!(f, "ndjson"),
+            Format::Markdown => write!(f, "markdown"),
+            Format::Html => write!(f, "html"),
+        }
+    }
+}
+
+/// Writes the rows of a query result in a format.
+///
+/// # Arguments
+///
+/// * `view` - The query result to write.
+/// * `format` - The format to write it in.
+/// * `writer` - The writer the result is written to.
+///
+/// # Returns
+///
+/// A `Result` indicating success or an I/O error.
+pub fn write_view<W: Write>(view: &View, format: Format, writer: &mut W) -> io::Result<()> {
+    match format {
+        Format::Table => write_table(view, writer),
+        Format::Box => write_box(view, writer),
+        Format::Csv => csv::write_view(view, writer, ','),
+        Format::Tsv => csv::write_view(view, writer, '\t'),
+        Format::Json => write_json(view, writer),
+        Format::Ndjson => write_ndjson(view, writer),
+        Format::Markdown => write_markdown(view, writer),
+        Format::Html => write_html(view, writer),
+    }
+}
+
+/// Writes the rows of a query result as a grid of columns separated by `|`, followed by the
+/// number of rows. The header of the columns is written even if there are no rows.
+///
+/// # Arguments
+///
+/// * `view` - The query result to write.
+/// * `writer` - The writer the grid is written to.
+///
+/// # Returns
+///
+/// A `Result` indicating success or an I/O error.
+pub fn write_table<W: Write>(view: &View, writer: &mut W) -> io::Result<()> {
+    let grid = Grid::new(view);
+
+    let line = |values: Vec<&str>| {
+        let values: Vec<String> = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| format!("{} | ", grid.pad(i, value)))
+            .collect();
+        values.concat()
+    };
+    writeln!(
+        writer,
+        "{}",
+        line(grid.columns.iter().map(|c| c.as_str()).collect())
+    )?;
+
+    let separator: Vec<String> = grid
+        .widths
+        .iter()
+        .enumerate()
+        .map(|(i, &width)| "-".repeat(width + if i == 0 { 1 } else { 2 }))
+        .collect();
+    writeln!(writer, "{}|", separator.join("|"))?;
+
+    for row in &grid.cells {
+        writeln!(writer, "{}", line(row.iter().map(String::as_str).collect()))?;
+    }
+
+    let count = view.rows.len();
+    writeln!(
+        writer,
+        "({} row{})",
+        count,
+        if count == 1 { "" } else { "s" }
+    )
+}
+
+/// Writes the rows of a query result as a grid drawn with box-drawing characters. The header
+/// of the columns is written even if there are no rows.
+///
+/// # Arguments
+///
+/// * `view` - The query result to write.
+/// * `writer` - The writer the grid is written to.
+///
+/// # Returns
+///
+/// A `Result` indicating success or an I/O error.
+pub fn write_box<W: Write>(view: &View, writer: &mut W) -> io::Result<()> {
+    let grid = Grid::new(view);
+
+    let rule = |left: &str, middle: &str, right: &str| {
+        let lines: Vec<String> = grid.widths.iter().map(|&w| "─".repeat(w + 2)).collect();
+        format!("{}{}{}", left, lines.join(middle), right)
+    };
+    let line = |values: Vec<&str>| {
+        let values: Vec<String> = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| format!(" {} ", grid.pad(i, value)))
+            .collect();
+        format!("│{}│", values.join("│"))
+    };
+
+    writeln!(writer, "{}", rule("┌", "┬", "┐"))?;
+    writeln!(
+        writer,
+        "{}",
+        line(grid.columns.iter().map(|c| c.as_str()).collect())
+    )?;
+    writeln!(writer, "{}", rule("├", "┼", "┤"))?;
+    for row in &grid.cells {
+        writeln!(writer, "{}", line(row.iter().map(String::as_str).collect()))?;
+    }
+    writeln!(writer, "{}", rule("└", "┴", "┘"))
+}
+
+/// Writes the rows of a query result as a JSON array of objects, one object per line.
+///
+/// Each object has a member for each selected column, in the order of the `SELECT` clause,
+/// which is `null` if the row has no value for the column.
+///
+/// # Arguments
+///
+/// * `view` - The query result to write.
+/// * `writer` - The writer the JSON is written to.
+///
+/// # Returns
+///
+/// A `Result` indicating success or an I/O error.
+pub fn write_json<W: Write>(view: &View, writer: &mut W) -> io::Result<()> {
+    let columns = view.column_names();
+    write!(writer, "[")?;
+    for (i, row) in view.rows.iter().enumerate() {
+        write!(writer, "{}\n  ", if i == 0 { "" } else { "," })?;
+        write_object(writer, &columns, row)?;
+    }
+    if !view.rows.is_empty() {
+        writeln!(writer)?;
+    }
+    writeln!(writer, "]")
+}
+
+/// Writes the rows of a query result as newline-delimited JSON, with an object for each row
+/// whose members are laid out as `write_json` does.
+///
+/// # Arguments
+///
+/// * `view` - The query result to write.
+/// * `writer` - The writer the JSON is written to.
+///
+/// # Returns
+///
+/// A `Result` indicating success or an I/O error.
+pub fn write_ndjson<W: Write>(view: &View, writer: &mut W) -> io::Result<()> {
+    let columns = view.column_names();
+    for row in &view.rows {
+        write_object(writer, &columns, row)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Writes the rows of a query result as a Markdown table.
+///
+/// `|` is escaped and line breaks are written as `<br>`, so every row stays on one line.
+///
+/// # Arguments
+///
+/// * `view` - The query result to write.
+/// * `writer` - The writer the table is written to.
+///
+/// # Returns
+///
+/// A `Result` indicating success or an I/O error.
+pub fn write_markdown<W: Write>(view: &View, writer: &mut W) -> io::Result<()> {
+    let columns = view.column_names();
+    if columns.is_empty() {
+        return Ok(());
+    }
+    let escape = |s: &str| {
+        s.replace('\\', "\\\\")
+            .replace('|', "\\|")
+            .replace("\r\n", "<br>")
+            .replace('\n', "<br>")
+    };
+
+    let header: Vec<String> = columns.iter().map(|c| escape(c)).collect();
+    writeln!(writer, "| {} |", header.join(" | "))?;
+    writeln!(writer, "|{}", " --- |".repeat(columns.len()))?;
+    for row in &view.rows {
+        let cells: Vec<String> = columns.iter().map(|c| escape(&cell(row, c))).collect();
+        writeln!(writer, "| {} |", cells.join(" | "))?;
+    }
+    Ok(())
+}
+
+/// Writes the rows of a query result as an HTML table.
+///
+/// # Arguments
+///
+/// * `view` - The query result to write.
+/// * `writer` - The writer the table is written to.
+///
+/// # Returns
+///
+/// A `Result` indicating success or an I/O error.
+pub fn write_html<W: Write>(view: &View, writer: &mut W) -> io::Result<()> {
+    let columns = view.column_names();
+
+    writeln!(writer, "<table>")?;
+    writeln!(writer, "<thead>")?;
+    write!(writer, "<tr>")?;
+    for column in &columns {
+        write!(writer, "<th>{}</th>", escape_html(column))?;
+    }
+    writeln!(writer, "</tr>")?;
+    writeln!(writer, "</thead>")?;
+    writeln!(writer, "<tbody>")?;
+    for row in &view.rows {
+        write!(writer, "<tr>")?;
+        for column in &columns {
+            write!(writer, "<td>{}</td>", escape_html(&cell(row, column)))?;
+        }
+        writeln!(writer, "</tr>")?;
+    }
+    writeln!(writer, "</tbody>")?;
+    writeln!(writer, "</table>")
+}
+
+/// The greatest display width of a value in the grid formats, beyond which it is truncated.
+const MAX_CELL_WIDTH: usize = 40;
+
+/// Represents the cells of a query result laid out in columns, as the grid formats draw them.
+struct Grid {
+    columns: Vec<String>,
+    /// The formatted values of each row, in the order of the columns.
+    cells: Vec<Vec<String>>,
+    /// The display width of each column.
+    widths: Vec<usize>,
+    /// Whether each column holds only numbers (or `NULL`), which are aligned to the right.
+    numeric: Vec<bool>,
+}
+
+impl Grid {
+    /// Lays out the rows of a query result in the selected columns.
+    ///
+    /// Values that a row does not have are shown as `NULL`, line breaks and tabs as spaces, and
+    /// values wider than `MAX_CELL_WIDTH` are truncated with `…`.
+    fn new(view: &View) -> Self {
+        let columns = view.column_names();
+        let cells: Vec<Vec<String>> = view
+            .rows
+            .iter()
+            .map(|row| {
+                columns
+                    .iter()
+                    .map(|c| truncate(&cell(row, c).replace(['\r', '\n', '\t'], " ")))
+                    .collect()
+            })
+            .collect();
+        let widths = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                cells
+                    .iter()
+                    .map(|row| row[i].width())
+                    .fold(column.width(), max)
+            })
+            .collect();
+        let numeric = columns
+            .iter()
+            .map(|column| {
+                view.rows.iter().all(|row| {
+                    matches!(
+                        row.get(column),
+                        None | Some(Value::Const(Const::Number(_) | Const::Null))
+                    )
+                })
+            })
+            .collect();
+
+        Grid {
+            columns,
+            cells,
+            widths,
+            numeric,
+        }
+    }
+
+    /// Pads a value to the width of its column, aligning it to the right in numeric columns.
+    fn pad(&self, column: usize, value: &str) -> String {
+        let padding = " ".repeat(self.widths[column] - value.width());
+        if self.numeric[column] {
+            format!("{}{}", padding, value)
+        } else {
+            format!("{}{}", value, padding)
+        }
+    }
+}
+
+/// Truncates a value to `MAX_CELL_WIDTH` display columns, ending it with `…` if it is cut.
+fn truncate(value: &str) -> String {
+    if value.width() <= MAX_CELL_WIDTH {
+        return value.to_string();
+    }
+    let mut truncated = String::new();
+    let mut width = 0;
+    for c in value.chars() {
+        width += c.width().unwrap_or(0);
+        if width > MAX_CELL_WIDTH - 1 {
+            break;
+        }
+        truncated.push(c);
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// Formats the value of a column in a row, which is `NULL` if the row does not have it.
+fn cell(row: &BTreeMap<String, Value>, column: &str) -> String {
+    row.get(column)
+        .map_or_else(|| "NULL".to_string(), Value::to_string)
+}
+
+/// Writes a row of a query result as a JSON object with a member for each column, in order.
+///
+/// A column the row has no value for is written as `null`, and a column selected more than
+/// once is written once, as an object cannot repeat a member.
+fn write_object<W: Write>(
+    writer: &mut W,
+    columns: &[String],
+    row: &BTreeMap<String, Value>,
+) -> io::Result<()> {
+    let mut written = BTreeSet::new();
+    write!(writer, "{{")?;
+    for column in columns {
+        if !written.insert(column) {
+            continue;
+        }
+        let value = match row.get(column) {
+            None => serde_json::Value::Null,
+            Some(value) => match value.get_const() {
+                Some(c) => c.to_serde_value(),
+                None => serde_json::Value::from(value.to_string()),
+            },
+        };
+        if written.len() > 1 {
+            write!(writer, ",")?;
+        }
+        serde_json::to_writer(&mut *writer, column)?;
+        write!(writer, ":")?;
+        serde_json::to_writer(&mut *writer, &value)?;
+    }
+    write!(writer, "}}")
+}
+
+/// Escapes the characters of a string that are special in HTML.
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Database, Table};
+    use crate::parser::parse_query;
+
+    fn view() -> View {
+        let row = |title: &str, year: Option<i64>| {
+            let mut row = BTreeMap::new();
+            row.insert(
+                "movies.title".to_string(),
+                Value::Const(Const::String(title.to_string())),
+            );
+            if let Some(year) = year {
+                row.insert("movies.year".to_string(), Value::Const(Const::Number(year)));
+            }
+            row
+        };
+        View {
+            columns: parse_query("SELECT movies.title, movies.year FROM movies")
+                .unwrap()
+                .select,
+            rows: vec![row("Heat", Some(1995)), row("<Up | \"Down\">", None)],
+        }
+    }
+
+    fn write(format: Format) -> String {
+        let mut out = Vec::new();
+        write_view(&view(), format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_formats() {
+        assert_eq!(
+            write(Format::Json),
+            "[\n  {\"movies.title\":\"Heat\",\"movies.year\":1995},\n  \
+             {\"movies.title\":\"<Up | \\\"Down\\\">\",\"movies.year\":null}\n]\n"
+        );
+        assert_eq!(
+            write(Format::Ndjson),
+            "{\"movies.title\":\"Heat\",\"movies.year\":1995}\n\
+             {\"movies.title\":\"<Up | \\\"Down\\\">\",\"movies.year\":null}\n"
+        );
+        assert_eq!(
+            write(Format::Markdown),
+            "| movies.title | movies.year |\n\
+             | --- | --- |\n\
+             | Heat | 1995 |\n\
+             | <Up \\| \"Down\"> | NULL |\n"
+        );
+        assert_eq!(
+            write(Format::Html),
+            "<table>\n<thead>\n<tr><th>movies.title</th><th>movies.year</th></tr>\n</thead>\n\
+             <tbody>\n<tr><td>Heat</td><td>1995</td></tr>\n\
+             <tr><td>&lt;Up | &quot;Down&quot;&gt;</td><td>NULL</td></tr>\n</tbody>\n</table>\n"
+        );
+        assert_eq!(
+            write(Format::Box),
+            "┌───────────────┬─────────────┐\n\
+             │ movies.title  │ movies.year │\n\
+             ├───────────────┼─────────────┤\n\
+             │ Heat          │        1995 │\n\
+             │ <Up | \"Down\"> │        NULL │\n\
+             └───────────────┴─────────────┘\n"
+        );
+
+        let empty = View {
+            columns: vec![],
+            rows: vec![],
+        };
+        let mut out = Vec::new();
+        write_json(&empty, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "[]\n");
+    }
+
+    /// Tests that every format writes the columns in the order they are selected.
+    #[test]
+    fn test_column_order() {
+        let mut view = view();
+        view.columns = parse_query("SELECT movies.year, movies.title, movies.year FROM movies")
+            .unwrap()
+            .select;
+        view.rows.truncate(1);
+        let write = |format| {
+            let mut out = Vec::new();
+            write_view(&view, format, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        assert_eq!(
+            write(Format::Ndjson),
+            "{\"movies.year\":1995,\"movies.title\":\"Heat\"}\n"
+        );
+        assert_eq!(
+            write(Format::Csv),
+            "movies.year,movies.title,movies.year\r\n1995,Heat,1995\r\n"
+        );
+        assert_eq!(
+            write(Format::Markdown),
+            "| movies.year | movies.title | movies.year |\n\
+             | --- | --- | --- |\n\
+             | 1995 | Heat | 1995 |\n"
+        );
+        assert!(write(Format::Table).starts_with("movies.year | movies.title | movies.year | \n"));
+    }
+
+    #[test]
+    fn test_write_table() {
+        let mut view = view();
+        view.columns = parse_query("SELECT movies.original, movies.title, movies.year FROM movies")
+            .unwrap()
+            .select;
+        view.rows[0].insert(
+            "movies.original".to_string(),
+            Value::Const(Const::String("千と千尋".to_string())),
+        );
+        view.rows[1].insert(
+            "movies.original".to_string(),
+            Value::Const(Const::String(format!("{}\nend", "x".repeat(50)))),
+        );
+        view.rows[1].insert("movies.year".to_string(), Value::Const(Const::Null));
+        let mut out = Vec::new();
+        write_table(&view, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "movies.original{} | movies.title  | movies.year | \n\
+                 {}|---------------|-------------|\n\
+                 千と千尋{} | Heat          |        1995 | \n\
+                 {}… | <Up | \"Down\"> |        NULL | \n\
+                 (2 rows)\n",
+                " ".repeat(25),
+                "-".repeat(41),
+                " ".repeat(32),
+                "x".repeat(39)
+            )
+        );
+
+        let mut out = Vec::new();
+        let empty = View {
+            columns: parse_query("SELECT movies.title FROM movies")
+                .unwrap()
+                .select,
+            rows: vec![],
+        };
+        write_table(&empty, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "movies.title | \n-------------|\n(0 rows)\n"
+        );
+        let mut out = Vec::new();
+        write_box(&empty, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "┌──────────────┐\n│ movies.title │\n├──────────────┤\n└──────────────┘\n"
+        );
+
+        // The columns come from the query, so a column that no row has is still shown.
+        let mut table = Table::new();
+        table
+            .add_row(
+                1,
+                [("title".to_string(), serde_json::Value::from("Up"))].into(),
+            )
+            .unwrap();
+        let mut db = Database::default();
+        db.tables.insert("movies".to_string(), table);
+        let view =
+            View::execute(parse_query("SELECT movies.nope FROM movies").unwrap(), &db).unwrap();
+        let mut out = Vec::new();
+        write_table(&view, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "movies.nope | \n------------|\n       NULL | \n(1 row)\n"
+        );
+    }
+}
@@ -1,60 +1,277 @@
+use std::collections::BTreeSet;
 use std::str::FromStr;
 
+use crate::database::DataType;
+
 /// Represents a parsed SQL query.
-#[derive(Debug)]
-pub struct Query<'a> {
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
     pub select: Vec<Column>,
+    /// The attached database the `from` table belongs to, if the query names one.
+    pub database: Option<String>,
     pub from: String,
     pub joins: Vec<Join>,
     pub where_clause: Option<ValueTest>,
-    input: Input<'a>,
+    /// The columns the rows are sorted by, most significant first.
+    pub order_by: Vec<OrderBy>,
+    /// The greatest number of rows to return.
+    pub limit: Option<usize>,
+}
+
+/// Represents a parsed SQL statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    /// A plain `SELECT` query.
+    Select(Query),
+    /// An `EXPLAIN` or `EXPLAIN ANALYZE` of a query.
+    Explain { query: Query, analyze: bool },
+    /// A `CREATE INDEX` statement.
+    CreateIndex(CreateIndex),
+    /// A `CREATE TABLE` statement.
+    CreateTable(CreateTable),
+    /// An `INSERT` statement.
+    Insert(Insert),
+    /// An `UPDATE` statement.
+    Update(Update),
+    /// A `DELETE` statement.
+    Delete(Delete),
+    /// A `DROP TABLE` statement.
+    DropTable(DropTable),
+    /// An `ALTER TABLE` statement.
+    AlterTable(AlterTable),
+    /// An `ATTACH` statement.
+    Attach(Attach),
+}
+
+/// Represents a `CREATE TABLE table_name (column-definition, ...)` statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateTable {
+    pub table_name: String,
+    pub columns: Vec<ColumnDefinition>,
+}
+
+/// Represents a `column_name TYPE [PRIMARY KEY] [NOT NULL] [DEFAULT const]` column definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnDefinition {
+    pub name: String,
+    pub data_type: DataType,
+    pub primary_key: bool,
+    pub not_null: bool,
+    pub default: Option<Const>,
+}
+
+/// Represents an `INSERT INTO table_name (columns) VALUES (values), ...` statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Insert {
+    pub table_name: String,
+    pub columns: Vec<String>,
+    /// The rows to insert, each holding one constant or parameter per column.
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// Represents a `CREATE INDEX index_name ON table_name(column_name)` statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateIndex {
+    pub index_name: String,
+    pub table_name: String,
+    pub column_name: String,
+}
+
+/// Represents an `UPDATE table_name SET column = value, ... [WHERE value-test]` statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Update {
+    pub table_name: String,
+    /// The columns to change and the values to assign to them.
+    pub assignments: Vec<(String, Value)>,
+    pub where_clause: Option<ValueTest>,
+}
+
+/// Represents a `DELETE FROM table_name [WHERE value-test]` statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delete {
+    pub table_name: String,
+    pub where_clause: Option<ValueTest>,
+}
+
+/// Represents an `ATTACH 'path' AS database_name` statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attach {
+    pub path: String,
+    pub database_name: String,
+}
+
+/// Represents a `DROP TABLE table_name` statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DropTable {
+    pub table_name: String,
+}
+
+/// Represents an `ALTER TABLE table_name action` statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlterTable {
+    pub table_name: String,
+    pub action: AlterAction,
+}
+
+/// Represents the change made to a table by an `ALTER TABLE` statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlterAction {
+    /// `ADD COLUMN column-definition`
+    Add(ColumnDefinition),
+    /// `DROP COLUMN column_name`
+    Drop(String),
+    /// `RENAME COLUMN column_name TO new_name`
+    Rename { from: String, to: String },
 }
 
 /// Represents the input string being parsed.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 struct Input<'a> {
     src: &'a str,
     pos: usize,
+    /// The greatest parameter number used so far by the statement being parsed.
+    params: usize,
 }
 
 /// Represents a column in a SQL query.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Column {
+    /// The attached database the column's table belongs to, if the column names one
+    /// (`music` in `music.albums.title`).
+    pub database: Option<String>,
     pub table_name: String,
     pub column_name: String,
 }
 
+impl Column {
+    /// Gets the name of the column's table, qualified by its database if the column names one.
+    pub fn qualified_table(&self) -> String {
+        qualified_name(self.database.as_deref(), &self.table_name)
+    }
+
+    /// Gets the name of the column qualified by its table, which is also the key of the
+    /// column's value in the rows of a view.
+    pub fn qualified_name(&self) -> String {
+        format!("{}.{}", self.qualified_table(), self.column_name)
+    }
+}
+
 /// Represents a JOIN clause in a SQL query.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Join {
+    /// The attached database the joined table belongs to, if the clause names one.
+    pub database: Option<String>,
     pub table_name: String,
     pub on: ValueTest,
 }
 
+impl Join {
+    /// Gets the name of the joined table, qualified by its database if the clause names one.
+    pub fn qualified_table(&self) -> String {
+        qualified_name(self.database.as_deref(), &self.table_name)
+    }
+}
+
+impl Query {
+    /// Gets the name of the `from` table, qualified by its database if the query names one.
+    pub fn qualified_from(&self) -> String {
+        qualified_name(self.database.as_deref(), &self.from)
+    }
+
+    /// Counts the parameters of the query, which is the greatest parameter number used.
+    pub fn parameter_count(&self) -> usize {
+        parameter_count(self.conditions().flat_map(|test| [&test.left, &test.right]))
+    }
+
+    /// Iterates over the conditions of the `JOIN` clauses and of the `WHERE` clause.
+    fn conditions(&self) -> impl Iterator<Item = &ValueTest> {
+        self.joins
+            .iter()
+            .map(|join| &join.on)
+            .chain(self.where_clause.as_ref())
+    }
+}
+
+/// Counts the parameters among values, which is the greatest parameter number used.
+fn parameter_count<'a>(values: impl Iterator<Item = &'a Value>) -> usize {
+    values
+        .filter_map(|value| match value {
+            Value::Param(number) => Some(*number),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Joins a table name to the name of the database it belongs to, if any.
+///
+/// # Arguments
+///
+/// * `database` - The name of the database, or `None` for an unqualified table name.
+/// * `table_name` - The name of the table.
+///
+/// # Returns
+///
+/// The table name, prefixed by `database.` if a database is given.
+pub fn qualified_name(database: Option<&str>, table_name: &str) -> String {
+    match database {
+        Some(database) => format!("{}.{}", database, table_name),
+        None => table_name.to_string(),
+    }
+}
+
+/// Splits a table name that may be qualified by its database (`music.albums`).
+///
+/// # Arguments
+///
+/// * `name` - The possibly qualified table name.
+///
+/// # Returns
+///
+/// The name of the database, if any, and the name of the table.
+pub(crate) fn split_table_name(name: &str) -> (Option<String>, String) {
+    match name.split_once('.') {
+        Some((database, table_name)) => (Some(database.to_string()), table_name.to_string()),
+        None => (None, name.to_string()),
+    }
+}
+
+/// Represents a column of an ORDER BY clause and the direction the rows are sorted in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBy {
+    pub column: Column,
+    /// Whether the rows are sorted from the greatest value (`DESC`) rather than the least.
+    pub descending: bool,
+}
+
 /// Represents a value test (e.g., a condition in a WHERE clause).
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ValueTest {
     pub left: Value,
     pub comparison: Comparison,
     pub right: Value,
 }
 
-/// Represents a value in a SQL query, which can be a column or a constant.
-#[derive(Debug, Clone)]
+/// Represents a value in a SQL query, which can be a column, a constant or a parameter.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Column(Column),
     Const(Const),
+    /// A placeholder (`?` or `$1`) for a constant bound when the statement is executed,
+    /// numbered from 1.
+    Param(usize),
 }
 
-/// Represents a constant value in a SQL query, which can be a number or a string.
-#[derive(Debug, Clone)]
+/// Represents a constant value in a SQL query, which can be a number, a string or `NULL`.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Const {
     Number(i64),
     String(String),
+    Null,
 }
 
 /// Represents a comparison operator in a SQL query.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Comparison {
     Eq,
     Gt,
@@ -81,61 +298,48 @@ impl FromStr for Comparison {
     }
 }
 
-impl<'a> Query<'a> {
-    /// Creates a new Query instance from an input string.
+impl Query {
+    /// Parses a SQL query starting at the current position of the input.
     ///
     /// # Arguments
     ///
-    /// * `input` - The SQL query string.
-    fn new(input: &'a str) -> Self {
-        Query {
-            select: Vec::new(),
-            from: String::new(),
-            joins: Vec::new(),
-            where_clause: None,
-            input: Input::new(input),
-        }
-    }
-
-    /// Parses the SQL query.
+    /// * `input` - The input, which is advanced past the query.
     ///
     /// # Returns
     ///
-    /// A result indicating success or failure.
-    fn parse(&mut self) -> Result<(), &'static str> {
-        self.parse_select()?;
-        self.parse_from()?;
-        self.parse_joins()?;
-        self.parse_where()?;
-        Ok(())
+    /// A result containing the parsed query or an error message.
+    fn parse(input: &mut Input) -> Result<Self, &'static str> {
+        let mut query = Query::default();
+        query.parse_select(input)?;
+        query.parse_from(input)?;
+        query.parse_joins(input)?;
+        query.parse_where(input)?;
+        query.parse_order_by(input)?;
+        query.parse_limit(input)?;
+        Ok(query)
     }
 
     /// Parses the SELECT clause of the SQL query.
     ///
+    /// # Arguments
+    ///
+    /// * `input` - The input, which is advanced past the clause.
+    ///
     /// # Returns
     ///
     /// A result indicating success or failure.
-    fn parse_select(&mut self) -> Result<(), &'static str> {
-        self.input.consume_whitespace();
-        self.input.expect("SELECT")?;
-        self.input.consume_whitespace();
+    fn parse_select(&mut self, input: &mut Input) -> Result<(), &'static str> {
+        input.consume_whitespace();
+        input.expect("SELECT")?;
+        input.consume_whitespace();
 
         loop {
-            let table_name = self.input.consume_until(".")?.to_string();
-            self.input.expect(".")?;
-            let column_name = self
-                .input
-                .consume_until_any(&[',', ' ', '\n'])?
-                .trim_matches(&['\r', '\n'][..])
-                .to_string();
-            self.select.push(Column {
-                table_name,
-                column_name,
-            });
-            self.input.consume_whitespace();
-            if self.input.peek() == Some(',') {
-                self.input.next();
-                self.input.consume_whitespace();
+            let column = input.consume_column(&[',', ' ', '\n', ';'])?;
+            self.select.push(column);
+            input.consume_whitespace();
+            if input.peek() == Some(',') {
+                input.next();
+                input.consume_whitespace();
             } else {
                 break;
             }
@@ -145,82 +349,62 @@ impl<'a> Query<'a> {
 
     /// Parses the FROM clause of the SQL query.
     ///
+    /// # Arguments
+    ///
+    /// * `input` - The input, which is advanced past the clause.
+    ///
     /// # Returns
     ///
     /// A result indicating success or failure.
-    fn parse_from(&mut self) -> Result<(), &'static str> {
-        self.input.consume_whitespace();
-        self.input.expect("FROM")?;
-        self.input.consume_whitespace();
-        self.from = self
-            .input
-            .consume_until_any(&[' ', '\n'])?
-            .trim_matches(&['\r', '\n'][..])
-            .to_string();
+    fn parse_from(&mut self, input: &mut Input) -> Result<(), &'static str> {
+        input.consume_whitespace();
+        input.expect("FROM")?;
+        input.consume_whitespace();
+        (self.database, self.from) = input.consume_table_name(&[' ', '\n', ';'])?;
         Ok(())
     }
 
     /// Parses the JOIN clauses of the SQL query.
     ///
+    /// # Arguments
+    ///
+    /// * `input` - The input, which is advanced past the clause.
+    ///
     /// # Returns
     ///
     /// A result indicating success or failure.
-    fn parse_joins(&mut self) -> Result<(), &'static str> {
-        self.input.consume_whitespace();
-        while self.input.peek() == Some('J') {
-            self.input.expect("JOIN")?;
-            self.input.consume_whitespace();
-
-            let table_name = self
-                .input
-                .consume_until_any(&[' ', '\n'])?
-                .trim_matches(&['\r', '\n'][..])
-                .to_string();
-            self.input.consume_whitespace();
+    fn parse_joins(&mut self, input: &mut Input) -> Result<(), &'static str> {
+        input.consume_whitespace();
+        while input.peek() == Some('J') {
+            input.expect("JOIN")?;
+            input.consume_whitespace();
 
-            self.input.expect("ON")?;
-            self.input.consume_whitespace();
+            let (database, table_name) = input.consume_table_name(&[' ', '\n'])?;
+            input.consume_whitespace();
 
-            let left_table = self.input.consume_until(".")?.to_string();
-            self.input.expect(".")?;
+            input.expect("ON")?;
+            input.consume_whitespace();
 
-            let left_column = self
-                .input
-                .consume_until_any(&[' ', '\n'])?
-                .trim_matches(&['\r', '\n'][..])
-                .to_string();
-            self.input.consume_whitespace();
+            let left = input.consume_column(&[' ', '\n'])?;
+            input.consume_whitespace();
 
-            let comparison = self
-                .input
+            let comparison = input
                 .consume_until_any(&[' ', '\n'])?
                 .trim_matches(&['\r', '\n'][..])
                 .to_string();
-            self.input.consume_whitespace();
+            input.consume_whitespace();
 
-            let right_table = self.input.consume_until(".")?.to_string();
-            self.input.expect(".")?;
-
-            let right_column = self
-                .input
-                .consume_until_any(&[' ', '\n'])?
-                .trim_matches(&['\r', '\n'][..])
-                .to_string();
-            self.input.consume_whitespace();
+            let right = input.consume_column(&[' ', '\n', ';'])?;
+            input.consume_whitespace();
 
             self.joins.push(Join {
-                table_name: table_name.to_string(),
+                database,
+                table_name,
                 on: ValueTest {
-                    left: Value::Column(Column {
-                        table_name: left_table,
-                        column_name: left_column.to_string(),
-                    }),
+                    left: Value::Column(left),
                     comparison: Comparison::from_str(&comparison)
                         .map_err(|_| "Invalid comparison operator")?,
-                    right: Value::Column(Column {
-                        table_name: right_table,
-                        column_name: right_column.to_string(),
-                    }),
+                    right: Value::Column(right),
                 },
             });
         }
@@ -229,188 +413,1008 @@ impl<'a> Query<'a> {
 
     /// Parses the WHERE clause of the SQL query.
     ///
+    /// # Arguments
+    ///
+    /// * `input` - The input, which is advanced past the clause.
+    ///
     /// # Returns
     ///
     /// A result indicating success or failure.
-    fn parse_where(&mut self) -> Result<(), &'static str> {
-        self.input.consume_whitespace();
-
-        if self.input.peek() == Some('W') {
-            self.input.expect("WHERE")?;
-            self.input.consume_whitespace();
-
-            // Parse the left value of the value-test
-            let left = self.parse_value()?;
-            self.input.consume_whitespace();
+    fn parse_where(&mut self, input: &mut Input) -> Result<(), &'static str> {
+        self.where_clause = input.consume_where()?;
+        Ok(())
+    }
 
-            // Parse the comparison operator
-            let comparison = self
-                .input
-                .consume_until(" ")?
-                .trim_matches(&['\r', '\n'][..])
-                .to_string();
-            self.input.consume_whitespace();
+    /// Parses the ORDER BY clause of the SQL query.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input, which is advanced past the clause.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success or failure.
+    fn parse_order_by(&mut self, input: &mut Input) -> Result<(), &'static str> {
+        input.consume_whitespace();
+        if !input.peek_keyword("ORDER") {
+            return Ok(());
+        }
+        input.expect("ORDER")?;
+        input.consume_whitespace();
+        input.expect("BY")?;
 
-            // Parse the right value of the value-test
-            let right = self.parse_value()?;
+        loop {
+            input.consume_whitespace();
+            let column = input.consume_column(&[',', ' ', '\n', '\r', ';'])?;
+            input.consume_whitespace();
+            let descending = if input.peek_keyword("DESC") {
+                input.expect("DESC")?;
+                true
+            } else {
+                if input.peek_keyword("ASC") {
+                    input.expect("ASC")?;
+                }
+                false
+            };
+            self.order_by.push(OrderBy { column, descending });
 
-            // Set the where_clause with the parsed ValueTest
-            self.where_clause = Some(ValueTest {
-                left,
-                comparison: Comparison::from_str(&comparison)
-                    .map_err(|_| "Invalid comparison operator")?,
-                right,
-            });
+            input.consume_whitespace();
+            if input.peek() == Some(',') {
+                input.next();
+            } else {
+                return Ok(());
+            }
         }
-        Ok(())
     }
 
-    /// Parses a value, which can be a column reference or a constant.
+    /// Parses the LIMIT clause of the SQL query.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input, which is advanced past the clause.
     ///
     /// # Returns
     ///
-    /// A result containing the parsed value or an error message.
-    fn parse_value(&mut self) -> Result<Value, &'static str> {
-        self.input.consume_whitespace();
-
-        if self.input.peek() == Some('\'') {
-            // Parse single-quoted string constant
-            self.input.expect("'")?;
-            let const_value = self.input.consume_until_any(&['\''])?.to_string();
-            self.input.expect("'")?;
-            Ok(Value::Const(Const::String(const_value)))
-        } else if self.input.peek().map_or(false, |c| c.is_digit(10)) {
-            // Parse numeric constant
-            let const_value = self
-                .input
-                .consume_until_any(&[' ', '\n', '\r'])?
-                .trim_matches(&['\r', '\n'][..])
-                .parse::<i64>()
-                .map_err(|_| "Failed to parse number")?;
-            Ok(Value::Const(Const::Number(const_value)))
-        } else {
-            // Parse column-id
-            let table_name = self
-                .input
-                .consume_until(".")?
-                .trim_matches(&['\r', '\n'][..])
-                .to_string();
-            self.input.expect(".")?;
-            let column_name = self
-                .input
-                .consume_until_any(&[' ', '\n', '\r'])?
-                .trim_matches(&['\r', '\n'][..])
-                .to_string();
-            Ok(Value::Column(Column {
-                table_name,
-                column_name,
-            }))
+    /// A result indicating success or failure.
+    fn parse_limit(&mut self, input: &mut Input) -> Result<(), &'static str> {
+        input.consume_whitespace();
+        if !input.peek_keyword("LIMIT") {
+            return Ok(());
+        }
+        input.expect("LIMIT")?;
+        input.consume_whitespace();
+
+        let start = input.pos;
+        while input.peek().is_some_and(|c| c.is_ascii_digit()) {
+            input.next();
         }
+        let limit = input.src[start..input.pos]
+            .parse::<usize>()
+            .map_err(|_| "Expected the number of rows after LIMIT")?;
+        self.limit = Some(limit);
+        Ok(())
     }
 }
 
-impl<'a> Input<'a> {
-    /// Creates a new Input instance from a source string.
+impl Statement {
+    /// Parses the statement starting at the current position of the input.
     ///
     /// # Arguments
     ///
-    /// * `src` - The source string.
-    fn new(src: &'a str) -> Self {
-        Input { src, pos: 0 }
+    /// * `input` - The input, which is advanced past the statement.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the parsed statement or an error message.
+    fn parse(input: &mut Input) -> Result<Self, &'static str> {
+        input.consume_whitespace();
+        input.params = 0;
+
+        if input.peek_keyword("CREATE") {
+            input.expect("CREATE")?;
+            input.consume_whitespace();
+            if input.peek_keyword("TABLE") {
+                return Ok(Statement::CreateTable(CreateTable::parse(input)?));
+            }
+            return Ok(Statement::CreateIndex(CreateIndex::parse(input)?));
+        }
+        if input.peek_keyword("DROP") {
+            return Ok(Statement::DropTable(DropTable::parse(input)?));
+        }
+        if input.peek_keyword("ALTER") {
+            return Ok(Statement::AlterTable(AlterTable::parse(input)?));
+        }
+        if input.peek_keyword("ATTACH") {
+            return Ok(Statement::Attach(Attach::parse(input)?));
+        }
+        if input.peek_keyword("INSERT") {
+            return Ok(Statement::Insert(Insert::parse(input)?));
+        }
+        if input.peek_keyword("UPDATE") {
+            return Ok(Statement::Update(Update::parse(input)?));
+        }
+        if input.peek_keyword("DELETE") {
+            return Ok(Statement::Delete(Delete::parse(input)?));
+        }
+
+        let mut analyze = None;
+        if input.peek_keyword("EXPLAIN") {
+            input.expect("EXPLAIN")?;
+            input.consume_whitespace();
+            analyze = Some(input.peek_keyword("ANALYZE"));
+            if analyze == Some(true) {
+                input.expect("ANALYZE")?;
+            }
+        }
+
+        let query = Query::parse(input)?;
+
+        Ok(match analyze {
+            Some(analyze) => Statement::Explain { query, analyze },
+            None => Statement::Select(query),
+        })
     }
 
-    /// Consumes whitespace characters from the input.
-    fn consume_whitespace(&mut self) {
-        while self
-            .peek()
-            .map_or(false, |c| c.is_whitespace() || c == '\r' || c == '\n')
-        {
-            self.next();
+    /// Counts the parameters of the statement, which is the greatest parameter number used.
+    pub fn parameter_count(&self) -> usize {
+        parameter_count(self.values())
+    }
+
+    /// Checks that the parameters of the statement skip no number, so constants can be bound
+    /// to them.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success, or an error message naming the skipped numbers.
+    pub fn check_parameters(&self) -> Result<(), String> {
+        let count = self.parameter_count();
+        let used: BTreeSet<usize> = self
+            .values()
+            .filter_map(|value| match value {
+                Value::Param(number) => Some(*number),
+                _ => None,
+            })
+            .collect();
+        let missing: Vec<String> = (1..count)
+            .filter(|number| !used.contains(number))
+            .map(|number| format!("${}", number))
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "Parameter ${} is used without {}",
+                count,
+                missing.join(", ")
+            ));
         }
+        Ok(())
     }
 
-    /// Returns the next character from the input, advancing the position.
-    fn next(&mut self) -> Option<char> {
-        if self.pos < self.src.len() {
-            let ch = self.src[self.pos..].chars().next().unwrap();
-            self.pos += ch.len_utf8();
-            Some(ch)
-        } else {
-            None
+    /// Binds constants to the parameters of the statement.
+    ///
+    /// The constants take the place of the parameters in the parsed statement rather than in its
+    /// text, so a constant is never read as SQL.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The constants, where the first is bound to parameter 1 (`$1`).
+    ///
+    /// # Returns
+    ///
+    /// A result containing the statement without parameters, or an error message if a
+    /// parameter number is skipped or the number of constants does not match the number of
+    /// parameters.
+    pub fn bind(&self, params: &[Const]) -> Result<Statement, String> {
+        self.check_parameters()?;
+        let count = self.parameter_count();
+        if params.len() != count {
+            return Err(format!(
+                "Expected {} parameter(s) but got {}",
+                count,
+                params.len()
+            ));
+        }
+
+        let mut statement = self.clone();
+        for value in statement.values_mut() {
+            if let Value::Param(number) = value {
+                *value = Value::Const(params[*number - 1].clone());
+            }
         }
+        Ok(statement)
     }
 
-    /// Peeks at the next character without advancing the position.
-    fn peek(&self) -> Option<char> {
-        self.src[self.pos..].chars().next()
+    /// Iterates over the values of the statement that may be parameters.
+    fn values(&self) -> impl Iterator<Item = &Value> {
+        let (tests, values): (Vec<&ValueTest>, Vec<&Value>) = match self {
+            Statement::Select(query) | Statement::Explain { query, .. } => {
+                (query.conditions().collect(), vec![])
+            }
+            Statement::Insert(insert) => (vec![], insert.rows.iter().flatten().collect()),
+            Statement::Update(update) => (
+                update.where_clause.iter().collect(),
+                update.assignments.iter().map(|(_, value)| value).collect(),
+            ),
+            Statement::Delete(delete) => (delete.where_clause.iter().collect(), vec![]),
+            _ => (vec![], vec![]),
+        };
+
+        tests
+            .into_iter()
+            .flat_map(|test| [&test.left, &test.right])
+            .chain(values)
     }
 
-    /// Consumes characters until the specified character is encountered.
+    /// Collects the values of the statement that may be parameters, to be replaced.
+    fn values_mut(&mut self) -> Vec<&mut Value> {
+        let (tests, values): (Vec<&mut ValueTest>, Vec<&mut Value>) = match self {
+            Statement::Select(query) | Statement::Explain { query, .. } => (
+                query
+                    .joins
+                    .iter_mut()
+                    .map(|join| &mut join.on)
+                    .chain(query.where_clause.as_mut())
+                    .collect(),
+                vec![],
+            ),
+            Statement::Insert(insert) => (vec![], insert.rows.iter_mut().flatten().collect()),
+            Statement::Update(update) => (
+                update.where_clause.iter_mut().collect(),
+                update
+                    .assignments
+                    .iter_mut()
+                    .map(|(_, value)| value)
+                    .collect(),
+            ),
+            Statement::Delete(delete) => (delete.where_clause.iter_mut().collect(), vec![]),
+            _ => (vec![], vec![]),
+        };
+
+        tests
+            .into_iter()
+            .flat_map(|test| [&mut test.left, &mut test.right])
+            .chain(values)
+            .collect()
+    }
+}
+
+impl From<i64> for Const {
+    fn from(n: i64) -> Self {
+        Const::Number(n)
+    }
+}
+
+impl From<&str> for Const {
+    fn from(s: &str) -> Self {
+        Const::String(s.to_string())
+    }
+}
+
+impl From<String> for Const {
+    fn from(s: String) -> Self {
+        Const::String(s)
+    }
+}
+
+impl<T: Into<Const>> From<Option<T>> for Const {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Const::Null, Into::into)
+    }
+}
+
+impl CreateIndex {
+    /// Parses a `CREATE INDEX` statement, following the `CREATE` keyword.
     ///
     /// # Arguments
     ///
-    /// * `until` - The character to consume until.
+    /// * `input` - The input, which is advanced past the statement.
     ///
     /// # Returns
     ///
-    /// A result containing the consumed string or an error message.
-    fn consume_until(&mut self, until: &str) -> Result<&'a str, &'static str> {
-        let start = self.pos;
-        while self.peek().map_or(false, |c| !until.contains(c)) {
-            self.next();
-        }
-        Ok(&self.src[start..self.pos])
+    /// A result containing the parsed statement or an error message.
+    fn parse(input: &mut Input) -> Result<Self, &'static str> {
+        input.expect("INDEX")?;
+        input.consume_whitespace();
+        let index_name = input.consume_identifier()?.to_string();
+        input.consume_whitespace();
+        input.expect("ON")?;
+        input.consume_whitespace();
+        let table_name = input.consume_identifier()?.to_string();
+        input.consume_whitespace();
+        input.expect("(")?;
+        input.consume_whitespace();
+        let column_name = input.consume_identifier()?.to_string();
+        input.consume_whitespace();
+        input.expect(")")?;
+
+        Ok(CreateIndex {
+            index_name,
+            table_name,
+            column_name,
+        })
     }
+}
 
-    /// Consumes characters until any of the specified characters are encountered.
+impl CreateTable {
+    /// Parses a `CREATE TABLE` statement, following the `CREATE` keyword.
     ///
     /// # Arguments
     ///
-    /// * `until` - A slice of characters to consume until.
+    /// * `input` - The input, which is advanced past the statement.
     ///
     /// # Returns
     ///
-    /// A result containing the consumed string or an error message.
-    fn consume_until_any(&mut self, until: &[char]) -> Result<&'a str, &'static str> {
-        let start = self.pos;
-        while self.peek().map_or(false, |c| !until.contains(&c)) {
-            self.next();
-        }
-        Ok(&self.src[start..self.pos])
+    /// A result containing the parsed statement or an error message.
+    fn parse(input: &mut Input) -> Result<Self, &'static str> {
+        input.expect("TABLE")?;
+        input.consume_whitespace();
+        let table_name = input.consume_identifier()?.to_string();
+        input.consume_whitespace();
+        let columns = input.consume_list(ColumnDefinition::parse)?;
+
+        Ok(CreateTable {
+            table_name,
+            columns,
+        })
     }
+}
 
-    /// Expects the next characters to match the specified string.
+impl ColumnDefinition {
+    /// Parses a column definition of a `CREATE TABLE` statement.
     ///
     /// # Arguments
     ///
-    /// * `expected` - The expected string.
+    /// * `input` - The input, which is advanced past the definition.
     ///
     /// # Returns
     ///
-    /// A result indicating success or failure.
-    fn expect(&mut self, expected: &str) -> Result<(), &'static str> {
-        for expected_char in expected.chars() {
-            if self.next() != Some(expected_char) {
-                return Err("Unexpected character");
+    /// A result containing the parsed definition or an error message.
+    fn parse(input: &mut Input) -> Result<Self, &'static str> {
+        let name = input.consume_identifier()?.to_string();
+        input.consume_whitespace();
+        let data_type = match input.consume_identifier()? {
+            "INTEGER" => DataType::Integer,
+            "TEXT" => DataType::Text,
+            _ => return Err("Unknown column type"),
+        };
+
+        let mut definition = ColumnDefinition {
+            name,
+            data_type,
+            primary_key: false,
+            not_null: false,
+            default: None,
+        };
+        loop {
+            input.consume_whitespace();
+            if input.peek_keyword("PRIMARY") {
+                input.expect("PRIMARY")?;
+                input.consume_whitespace();
+                input.expect("KEY")?;
+                definition.primary_key = true;
+            } else if input.peek_keyword("NOT") {
+                input.expect("NOT")?;
+                input.consume_whitespace();
+                input.expect("NULL")?;
+                definition.not_null = true;
+            } else if input.peek_keyword("DEFAULT") {
+                input.expect("DEFAULT")?;
+                input.consume_whitespace();
+                definition.default = Some(input.consume_const()?);
+            } else {
+                return Ok(definition);
             }
         }
-        Ok(())
     }
 }
 
-/// Parses an SQL query string into a Query instance.
-///
-/// # Arguments
-///
-/// * `input` - The SQL query string.
+impl Insert {
+    /// Parses an `INSERT` statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input, which is advanced past the statement.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the parsed statement or an error message.
+    fn parse(input: &mut Input) -> Result<Self, &'static str> {
+        input.expect("INSERT")?;
+        input.consume_whitespace();
+        input.expect("INTO")?;
+        input.consume_whitespace();
+        let table_name = input.consume_identifier()?.to_string();
+        input.consume_whitespace();
+
+        let columns = input.consume_list(|input| Ok(input.consume_identifier()?.to_string()))?;
+        if columns.iter().collect::<BTreeSet<_>>().len() != columns.len() {
+            return Err("A column is listed more than once");
+        }
+        input.consume_whitespace();
+        input.expect("VALUES")?;
+
+        let mut rows = vec![];
+        loop {
+            input.consume_whitespace();
+            let row = input.consume_list(|input| match input.peek() {
+                Some('?' | '$') => Ok(Value::Param(input.consume_param()?)),
+                _ => Ok(Value::Const(input.consume_const()?)),
+            })?;
+            if row.len() != columns.len() {
+                return Err("Number of values does not match number of columns");
+            }
+            rows.push(row);
+
+            input.consume_whitespace();
+            if input.peek() == Some(',') {
+                input.next();
+            } else {
+                break;
+            }
+        }
+
+        Ok(Insert {
+            table_name,
+            columns,
+            rows,
+        })
+    }
+}
+
+impl Update {
+    /// Parses an `UPDATE` statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input, which is advanced past the statement.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the parsed statement or an error message.
+    fn parse(input: &mut Input) -> Result<Self, &'static str> {
+        input.expect("UPDATE")?;
+        input.consume_whitespace();
+        let table_name = input.consume_identifier()?.to_string();
+        input.consume_whitespace();
+        input.expect("SET")?;
+
+        let mut assignments = vec![];
+        loop {
+            input.consume_whitespace();
+            let column_name = input.consume_identifier()?.to_string();
+            input.consume_whitespace();
+            input.expect("=")?;
+            let value = input.consume_value()?;
+            assignments.push((column_name, value));
+
+            input.consume_whitespace();
+            if input.peek() == Some(',') {
+                input.next();
+            } else {
+                break;
+            }
+        }
+
+        Ok(Update {
+            table_name,
+            assignments,
+            where_clause: input.consume_where()?,
+        })
+    }
+}
+
+impl Delete {
+    /// Parses a `DELETE` statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input, which is advanced past the statement.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the parsed statement or an error message.
+    fn parse(input: &mut Input) -> Result<Self, &'static str> {
+        input.expect("DELETE")?;
+        input.consume_whitespace();
+        input.expect("FROM")?;
+        input.consume_whitespace();
+        let table_name = input.consume_identifier()?.to_string();
+
+        Ok(Delete {
+            table_name,
+            where_clause: input.consume_where()?,
+        })
+    }
+}
+
+impl Attach {
+    /// Parses an `ATTACH` statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input, which is advanced past the statement.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the parsed statement or an error message.
+    fn parse(input: &mut Input) -> Result<Self, &'static str> {
+        input.expect("ATTACH")?;
+        input.consume_whitespace();
+        let Const::String(path) = input.consume_const()? else {
+            return Err("Expected a quoted path");
+        };
+        input.consume_whitespace();
+        input.expect("AS")?;
+        input.consume_whitespace();
+        let database_name = input.consume_identifier()?.to_string();
+
+        Ok(Attach {
+            path,
+            database_name,
+        })
+    }
+}
+
+impl DropTable {
+    /// Parses a `DROP TABLE` statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input, which is advanced past the statement.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the parsed statement or an error message.
+    fn parse(input: &mut Input) -> Result<Self, &'static str> {
+        input.expect("DROP")?;
+        input.consume_whitespace();
+        input.expect("TABLE")?;
+        input.consume_whitespace();
+        let table_name = input.consume_identifier()?.to_string();
+
+        Ok(DropTable { table_name })
+    }
+}
+
+impl AlterTable {
+    /// Parses an `ALTER TABLE` statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input, which is advanced past the statement.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the parsed statement or an error message.
+    fn parse(input: &mut Input) -> Result<Self, &'static str> {
+        input.expect("ALTER")?;
+        input.consume_whitespace();
+        input.expect("TABLE")?;
+        input.consume_whitespace();
+        let table_name = input.consume_identifier()?.to_string();
+        input.consume_whitespace();
+
+        let action = if input.peek_keyword("ADD") {
+            input.expect("ADD")?;
+            input.consume_whitespace();
+            input.expect("COLUMN")?;
+            input.consume_whitespace();
+            AlterAction::Add(ColumnDefinition::parse(input)?)
+        } else if input.peek_keyword("DROP") {
+            input.expect("DROP")?;
+            input.consume_whitespace();
+            input.expect("COLUMN")?;
+            input.consume_whitespace();
+            AlterAction::Drop(input.consume_identifier()?.to_string())
+        } else {
+            input.expect("RENAME")?;
+            input.consume_whitespace();
+            input.expect("COLUMN")?;
+            input.consume_whitespace();
+            let from = input.consume_identifier()?.to_string();
+            input.consume_whitespace();
+            input.expect("TO")?;
+            input.consume_whitespace();
+            let to = input.consume_identifier()?.to_string();
+            AlterAction::Rename { from, to }
+        };
+
+        Ok(AlterTable { table_name, action })
+    }
+}
+
+impl<'a> Input<'a> {
+    /// Creates a new Input instance from a source string.
+    ///
+    /// # Arguments
+    ///
+    /// * `src` - The source string.
+    fn new(src: &'a str) -> Self {
+        Input {
+            src,
+            pos: 0,
+            params: 0,
+        }
+    }
+
+    /// Checks whether the remaining input starts with the specified keyword.
+    ///
+    /// # Arguments
+    ///
+    /// * `keyword` - The keyword to look for.
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        let rest = &self.src[self.pos..];
+        rest.starts_with(keyword)
+            && !rest[keyword.len()..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_')
+    }
+
+    /// Consumes whitespace characters from the input.
+    fn consume_whitespace(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|c| c.is_whitespace() || c == '\r' || c == '\n')
+        {
+            self.next();
+        }
+    }
+
+    /// Returns the next character from the input, advancing the position.
+    fn next(&mut self) -> Option<char> {
+        if self.pos < self.src.len() {
+            let ch = self.src[self.pos..].chars().next().unwrap();
+            self.pos += ch.len_utf8();
+            Some(ch)
+        } else {
+            None
+        }
+    }
+
+    /// Peeks at the next character without advancing the position.
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    /// Consumes a table, column or index name.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the consumed name or an error message.
+    fn consume_identifier(&mut self) -> Result<&'a str, &'static str> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            self.next();
+        }
+        if start == self.pos {
+            return Err("Expected a name");
+        }
+        Ok(&self.src[start..self.pos])
+    }
+
+    /// Consumes a table name, which may be qualified by the name of a database.
+    ///
+    /// # Arguments
+    ///
+    /// * `until` - The characters that end the name.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the name of the database, if any, and the name of the table, or an
+    /// error message if either is empty.
+    fn consume_table_name(
+        &mut self,
+        until: &[char],
+    ) -> Result<(Option<String>, String), &'static str> {
+        let name = self
+            .consume_until_any(until)?
+            .trim_matches(&['\r', '\n'][..]);
+        let (database, table_name) = split_table_name(name);
+        if table_name.is_empty() || database.as_deref() == Some("") {
+            return Err("Expected a table name");
+        }
+        Ok((database, table_name))
+    }
+
+    /// Consumes an optional WHERE clause.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the condition of the clause, if present, or an error message.
+    fn consume_where(&mut self) -> Result<Option<ValueTest>, &'static str> {
+        self.consume_whitespace();
+
+        if self.peek() == Some('W') {
+            self.expect("WHERE")?;
+            self.consume_whitespace();
+
+            // Parse the left value of the value-test
+            let left = self.consume_value()?;
+            self.consume_whitespace();
+
+            // Parse the comparison operator
+            let comparison = self
+                .consume_until(" ")?
+                .trim_matches(&['\r', '\n'][..])
+                .to_string();
+            self.consume_whitespace();
+
+            // Parse the right value of the value-test
+            let right = self.consume_value()?;
+
+            return Ok(Some(ValueTest {
+                left,
+                comparison: Comparison::from_str(&comparison)
+                    .map_err(|_| "Invalid comparison operator")?,
+                right,
+            }));
+        }
+        Ok(None)
+    }
+
+    /// Consumes a value, which can be a column reference, a constant or a parameter.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the consumed value or an error message.
+    fn consume_value(&mut self) -> Result<Value, &'static str> {
+        self.consume_whitespace();
+
+        if self.peek().is_some_and(|c| c == '?' || c == '$') {
+            Ok(Value::Param(self.consume_param()?))
+        } else if self.peek_keyword("NULL")
+            || self
+                .peek()
+                .is_some_and(|c| c == '\'' || c == '-' || c.is_ascii_digit())
+        {
+            // Parse string or numeric constant
+            Ok(Value::Const(self.consume_const()?))
+        } else {
+            // Parse column-id
+            Ok(Value::Column(
+                self.consume_column(&[' ', '\n', '\r', ';', ','])?,
+            ))
+        }
+    }
+
+    /// Consumes a column-id, which is a column name qualified by a table name that may
+    /// itself be qualified by a database name (`music.albums.title`).
+    ///
+    /// # Arguments
+    ///
+    /// * `until` - The characters that end the column-id.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the consumed column or an error message.
+    fn consume_column(&mut self, until: &[char]) -> Result<Column, &'static str> {
+        let column_id = self
+            .consume_until_any(until)?
+            .trim_matches(&['\r', '\n'][..]);
+        let (table_name, column_name) = column_id
+            .rsplit_once('.')
+            .ok_or("Expected a column qualified by its table")?;
+        let (database, table_name) = split_table_name(table_name);
+
+        Ok(Column {
+            database,
+            table_name,
+            column_name: column_name.to_string(),
+        })
+    }
+
+    /// Consumes a parameter, which is either `?`, numbered after the greatest parameter of the
+    /// statement so far, or `$` followed by its number.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the number of the parameter or an error message.
+    fn consume_param(&mut self) -> Result<usize, &'static str> {
+        let number = match self.next() {
+            Some('?') => self.params + 1,
+            Some('$') => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    self.next();
+                }
+                match self.src[start..self.pos].parse::<usize>() {
+                    Ok(number) if number > 0 => number,
+                    _ => return Err("Expected a parameter number"),
+                }
+            }
+            _ => return Err("Expected a parameter"),
+        };
+        self.params = self.params.max(number);
+        Ok(number)
+    }
+
+    /// Consumes a constant, which is either a number, a single-quoted string or `NULL`.
+    ///
+    /// Inside a string, a quote is written as two quotes (`'It''s'`).
+    ///
+    /// # Returns
+    ///
+    /// A result containing the consumed constant or an error message.
+    fn consume_const(&mut self) -> Result<Const, &'static str> {
+        if self.peek_keyword("NULL") {
+            self.expect("NULL")?;
+            return Ok(Const::Null);
+        }
+
+        if self.peek() == Some('\'') {
+            self.expect("'")?;
+            let mut value = String::new();
+            loop {
+                value.push_str(self.consume_until_any(&['\''])?);
+                self.expect("'").map_err(|_| "Unterminated string")?;
+                if self.peek() == Some('\'') {
+                    self.next();
+                    value.push('\'');
+                } else {
+                    return Ok(Const::String(value));
+                }
+            }
+        }
+
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.next();
+        }
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.next();
+        }
+        self.src[start..self.pos]
+            .parse::<i64>()
+            .map(Const::Number)
+            .map_err(|_| "Failed to parse number")
+    }
+
+    /// Consumes a parenthesized, comma-separated list.
+    ///
+    /// # Arguments
+    ///
+    /// * `consume_item` - Consumes a single item of the list.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the consumed items or an error message.
+    fn consume_list<T>(
+        &mut self,
+        consume_item: impl Fn(&mut Self) -> Result<T, &'static str>,
+    ) -> Result<Vec<T>, &'static str> {
+        self.expect("(")?;
+        let mut items = vec![];
+        loop {
+            self.consume_whitespace();
+            items.push(consume_item(self)?);
+            self.consume_whitespace();
+            match self.next() {
+                Some(',') => {}
+                Some(')') => return Ok(items),
+                _ => return Err("Expected ',' or ')'"),
+            }
+        }
+    }
+
+    /// Consumes characters until the specified character is encountered.
+    ///
+    /// # Arguments
+    ///
+    /// * `until` - The character to consume until.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the consumed string or an error message.
+    fn consume_until(&mut self, until: &str) -> Result<&'a str, &'static str> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| !until.contains(c)) {
+            self.next();
+        }
+        Ok(&self.src[start..self.pos])
+    }
+
+    /// Consumes characters until any of the specified characters are encountered.
+    ///
+    /// # Arguments
+    ///
+    /// * `until` - A slice of characters to consume until.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the consumed string or an error message.
+    fn consume_until_any(&mut self, until: &[char]) -> Result<&'a str, &'static str> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| !until.contains(&c)) {
+            self.next();
+        }
+        Ok(&self.src[start..self.pos])
+    }
+
+    /// Expects the next characters to match the specified string.
+    ///
+    /// # Arguments
+    ///
+    /// * `expected` - The expected string.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success or failure.
+    fn expect(&mut self, expected: &str) -> Result<(), &'static str> {
+        for expected_char in expected.chars() {
+            if self.next() != Some(expected_char) {
+                return Err("Unexpected character");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses an SQL query string into a Query instance.
+///
+/// # Arguments
+///
+/// * `input` - The SQL query string, optionally terminated by `;`.
 ///
 /// # Returns
 ///
-/// A parsed Query instance.
-pub fn parse_query(input: &str) -> Query {
-    let mut parsed_query = Query::new(input);
-    parsed_query.parse().unwrap();
-    parsed_query
+/// A result containing the parsed query or an error message, also if the input is another
+/// kind of statement.
+pub fn parse_query(input: &str) -> Result<Query, &'static str> {
+    match parse_statement(input)? {
+        Statement::Select(query) => Ok(query),
+        _ => Err("Expected a SELECT query"),
+    }
+}
+
+/// Parses an SQL statement string into a Statement instance.
+///
+/// # Arguments
+///
+/// * `input` - The SQL statement string, optionally terminated by `;`.
+///
+/// # Returns
+///
+/// A result containing the parsed statement or an error message.
+pub fn parse_statement(input: &str) -> Result<Statement, &'static str> {
+    let mut statements = parse_statements(input)?;
+    if statements.len() != 1 {
+        return Err("Expected exactly one statement");
+    }
+    Ok(statements.remove(0))
+}
+
+/// Parses a script of SQL statements separated by `;`.
+///
+/// # Arguments
+///
+/// * `input` - The SQL script.
+///
+/// # Returns
+///
+/// A result containing the parsed statements, in order, or an error message.
+pub fn parse_statements(input: &str) -> Result<Vec<Statement>, &'static str> {
+    let mut input = Input::new(input);
+    let mut statements = vec![];
+
+    loop {
+        input.consume_whitespace();
+        if input.peek().is_none() {
+            break;
+        }
+
+        statements.push(Statement::parse(&mut input)?);
+
+        input.consume_whitespace();
+        match input.next() {
+            Some(';') | None => {}
+            Some(_) => return Err("Expected ';' after statement"),
+        }
+    }
+
+    Ok(statements)
 }
 
 #[cfg(test)]
@@ -420,8 +1424,9 @@ mod tests {
     /// Tests parsing of the SELECT clause.
     #[test]
     fn test_parse_select() {
-        let mut query = Query::new("SELECT table1.col1, table2.col2 FROM table1");
-        query.parse_select().unwrap();
+        let mut input = Input::new("SELECT table1.col1, table2.col2 FROM table1");
+        let mut query = Query::default();
+        query.parse_select(&mut input).unwrap();
         assert_eq!(query.select.len(), 2);
         assert_eq!(query.select[0].table_name, "table1");
         assert_eq!(query.select[0].column_name, "col1");
@@ -432,22 +1437,26 @@ mod tests {
     /// Tests parsing of the FROM clause.
     #[test]
     fn test_parse_from() {
-        let mut query = Query::new("FROM table1");
-        query.parse_from().unwrap();
+        let mut input = Input::new("FROM table1");
+        let mut query = Query::default();
+        query.parse_from(&mut input).unwrap();
         assert_eq!(query.from, "table1");
     }
 
     /// Tests parsing of the WHERE clause.
     #[test]
     fn test_parse_where() {
-        let mut query = Query::new("WHERE table1.col1 = 42");
-        query.parse_where().unwrap();
+        let mut input = Input::new("WHERE table1.col1 = 42");
+        let mut query = Query::default();
+        query.parse_where(&mut input).unwrap();
         let where_clause = query.where_clause.unwrap();
         match where_clause.left {
             Value::Column(Column {
+                database,
                 table_name,
                 column_name,
             }) => {
+                assert_eq!(database, None);
                 assert_eq!(table_name, "table1");
                 assert_eq!(column_name, "col1");
             }
@@ -459,4 +1468,378 @@ mod tests {
             _ => panic!("Expected number constant"),
         }
     }
+
+    /// Tests parsing of the ORDER BY and LIMIT clauses.
+    #[test]
+    fn test_parse_order_by_and_limit() {
+        let query = parse_query(
+            "SELECT movies.title FROM movies WHERE movies.cert > 12 ORDER BY movies.cert DESC, movies.title LIMIT 2",
+        ).unwrap();
+        assert_eq!(
+            query.order_by,
+            vec![
+                OrderBy {
+                    column: Column {
+                        database: None,
+                        table_name: "movies".to_string(),
+                        column_name: "cert".to_string(),
+                    },
+                    descending: true,
+                },
+                OrderBy {
+                    column: Column {
+                        database: None,
+                        table_name: "movies".to_string(),
+                        column_name: "title".to_string(),
+                    },
+                    descending: false,
+                },
+            ]
+        );
+        assert_eq!(query.limit, Some(2));
+
+        let query =
+            parse_query("SELECT movies.title FROM movies ORDER BY movies.title ASC").unwrap();
+        assert_eq!(query.order_by.len(), 1);
+        assert_eq!(query.limit, None);
+
+        assert!(parse_statement("SELECT movies.title FROM movies ORDER movies.title").is_err());
+        assert!(parse_statement("SELECT movies.title FROM movies LIMIT all").is_err());
+    }
+
+    /// Tests that parsing a query reports invalid SQL and other statements as errors.
+    #[test]
+    fn test_parse_query_errors() {
+        assert!(parse_query("SELECT movies.title FROM movies;").is_ok());
+        assert_eq!(
+            parse_query("SELECT movies.title FROM movies WHERE"),
+            Err("Expected a column qualified by its table")
+        );
+        assert_eq!(
+            parse_query("SELECT movies.title FROM movies; SELECT movies.id FROM movies"),
+            Err("Expected exactly one statement")
+        );
+        assert_eq!(
+            parse_query("DELETE FROM movies"),
+            Err("Expected a SELECT query")
+        );
+    }
+
+    /// Tests that a query must name the tables it reads from.
+    #[test]
+    fn test_parse_missing_table_name() {
+        for query in [
+            "SELECT movies.title FROM",
+            "SELECT movies.title FROM ;",
+            "SELECT movies.title FROM music.",
+            "SELECT movies.title FROM .movies",
+            "SELECT movies.title FROM movies JOIN",
+            "SELECT movies.title FROM movies JOIN music. ON movies.id = music.albums.id",
+        ] {
+            assert_eq!(
+                parse_statement(query),
+                Err("Expected a table name"),
+                "{}",
+                query
+            );
+        }
+    }
+
+    /// Tests parsing of EXPLAIN and EXPLAIN ANALYZE statements.
+    #[test]
+    fn test_parse_explain() {
+        match parse_statement("EXPLAIN SELECT movies.title FROM movies").unwrap() {
+            Statement::Explain { query, analyze } => {
+                assert!(!analyze);
+                assert_eq!(query.from, "movies");
+            }
+            _ => panic!("Expected EXPLAIN statement"),
+        }
+        match parse_statement("EXPLAIN ANALYZE SELECT movies.title FROM movies").unwrap() {
+            Statement::Explain { query, analyze } => {
+                assert!(analyze);
+                assert_eq!(query.select[0].column_name, "title");
+            }
+            _ => panic!("Expected EXPLAIN ANALYZE statement"),
+        }
+        assert!(matches!(
+            parse_statement("SELECT movies.title FROM movies").unwrap(),
+            Statement::Select(_)
+        ));
+    }
+
+    /// Tests parsing of CREATE INDEX statements.
+    #[test]
+    fn test_parse_create_index() {
+        match parse_statement("CREATE INDEX idx_cert ON movies (cert);").unwrap() {
+            Statement::CreateIndex(create_index) => {
+                assert_eq!(create_index.index_name, "idx_cert");
+                assert_eq!(create_index.table_name, "movies");
+                assert_eq!(create_index.column_name, "cert");
+            }
+            _ => panic!("Expected CREATE INDEX statement"),
+        }
+        assert!(parse_statement("CREATE INDEX ON movies(cert)").is_err());
+    }
+
+    /// Tests parsing of scripts with several statements.
+    #[test]
+    fn test_parse_statements() {
+        let statements = parse_statements(
+            "CREATE INDEX idx_cert ON movies(cert);\nSELECT movies.title FROM movies WHERE movies.cert = 15;\nSELECT movies.title FROM movies;\n",
+        )
+        .unwrap();
+        assert_eq!(statements.len(), 3);
+        match &statements[1] {
+            Statement::Select(query) => assert!(query.where_clause.is_some()),
+            _ => panic!("Expected SELECT statement"),
+        }
+        match &statements[2] {
+            Statement::Select(query) => assert_eq!(query.from, "movies"),
+            _ => panic!("Expected SELECT statement"),
+        }
+        assert!(
+            parse_statement("SELECT movies.title FROM movies; SELECT movies.id FROM movies")
+                .is_err()
+        );
+    }
+
+    /// Tests parsing of INSERT statements.
+    #[test]
+    fn test_parse_insert() {
+        match parse_statement(
+            "INSERT INTO movies (id, title, cert) VALUES (4, 'Schindler''s List', 15), (5, 'Up', -1)",
+        )
+        .unwrap()
+        {
+            Statement::Insert(insert) => {
+                assert_eq!(insert.table_name, "movies");
+                assert_eq!(insert.columns, vec!["id", "title", "cert"]);
+                assert_eq!(insert.rows.len(), 2);
+                match &insert.rows[0][1] {
+                    Value::Const(Const::String(s)) => assert_eq!(s, "Schindler's List"),
+                    _ => panic!("Expected string constant"),
+                }
+                match insert.rows[1][2] {
+                    Value::Const(Const::Number(n)) => assert_eq!(n, -1),
+                    _ => panic!("Expected number constant"),
+                }
+            }
+            _ => panic!("Expected INSERT statement"),
+        }
+        assert!(parse_statement("INSERT INTO movies (id, title) VALUES (4)").is_err());
+        assert!(parse_statement("INSERT INTO movies (id) VALUES ('Up)").is_err());
+        assert_eq!(
+            parse_statement("INSERT INTO movies (title, title) VALUES ('a', 'b')").unwrap_err(),
+            "A column is listed more than once"
+        );
+    }
+
+    /// Tests parsing and binding of parameters.
+    #[test]
+    fn test_parse_params() {
+        let statement = parse_statement(
+            "INSERT INTO movies (title, cert, year) VALUES (?, $2, ?), ('Up', ?, NULL)",
+        )
+        .unwrap();
+        match &statement {
+            Statement::Insert(insert) => {
+                let numbers: Vec<Option<usize>> = insert
+                    .rows
+                    .iter()
+                    .flatten()
+                    .map(|value| match value {
+                        Value::Param(number) => Some(*number),
+                        _ => None,
+                    })
+                    .collect();
+                assert_eq!(numbers, [Some(1), Some(2), Some(3), None, Some(4), None]);
+            }
+            _ => panic!("Expected INSERT statement"),
+        }
+        assert_eq!(statement.parameter_count(), 4);
+        assert!(statement.check_parameters().is_ok());
+
+        let statement =
+            parse_statement("SELECT movies.title FROM movies WHERE movies.title = $1").unwrap();
+        assert_eq!(statement.parameter_count(), 1);
+        match statement.bind(&["It's".into()]).unwrap() {
+            Statement::Select(query) => match query.where_clause.unwrap().right {
+                Value::Const(Const::String(s)) => assert_eq!(s, "It's"),
+                _ => panic!("Expected string constant"),
+            },
+            _ => panic!("Expected SELECT statement"),
+        }
+        assert!(statement.bind(&[]).is_err());
+        assert!(statement.bind(&[1.into(), 2.into()]).is_err());
+
+        let statement = parse_statement("UPDATE movies SET title = ? WHERE movies.id = ?").unwrap();
+        assert_eq!(statement.parameter_count(), 2);
+        assert!(parse_statement("SELECT movies.title FROM movies WHERE movies.id = $0").is_err());
+
+        let statement =
+            parse_statement("SELECT movies.title FROM movies WHERE movies.id = $3").unwrap();
+        assert_eq!(
+            statement.check_parameters().unwrap_err(),
+            "Parameter $3 is used without $1, $2"
+        );
+        assert!(statement.bind(&[1.into(), 2.into(), 3.into()]).is_err());
+        let statement =
+            parse_statement("INSERT INTO movies (title, cert) VALUES ($1, $3)").unwrap();
+        assert_eq!(
+            statement.bind(&["Up".into(), 12.into()]).unwrap_err(),
+            "Parameter $3 is used without $2"
+        );
+        assert!(parse_statement("SELECT movies.title FROM movies WHERE movies.id = $").is_err());
+    }
+
+    /// Tests parsing of UPDATE statements.
+    #[test]
+    fn test_parse_update() {
+        match parse_statement("UPDATE movies SET cert = 12, title = 'Aliens' WHERE movies.id = 3")
+            .unwrap()
+        {
+            Statement::Update(update) => {
+                assert_eq!(update.table_name, "movies");
+                assert_eq!(update.assignments.len(), 2);
+                assert_eq!(update.assignments[0].0, "cert");
+                assert!(matches!(
+                    update.assignments[1].1,
+                    Value::Const(Const::String(_))
+                ));
+                assert!(update.where_clause.is_some());
+            }
+            _ => panic!("Expected UPDATE statement"),
+        }
+        match parse_statement("UPDATE movies SET title = movies.original_title").unwrap() {
+            Statement::Update(update) => {
+                assert!(matches!(update.assignments[0].1, Value::Column(_)));
+                assert!(update.where_clause.is_none());
+            }
+            _ => panic!("Expected UPDATE statement"),
+        }
+    }
+
+    /// Tests parsing of DELETE statements.
+    #[test]
+    fn test_parse_delete() {
+        match parse_statement("DELETE FROM movies WHERE movies.cert > 15;").unwrap() {
+            Statement::Delete(delete) => {
+                assert_eq!(delete.table_name, "movies");
+                assert_eq!(delete.where_clause.unwrap().comparison, Comparison::Gt);
+            }
+            _ => panic!("Expected DELETE statement"),
+        }
+        match parse_statement("DELETE FROM movies").unwrap() {
+            Statement::Delete(delete) => assert!(delete.where_clause.is_none()),
+            _ => panic!("Expected DELETE statement"),
+        }
+    }
+
+    /// Tests parsing of CREATE TABLE statements.
+    #[test]
+    fn test_parse_create_table() {
+        match parse_statement(
+            "CREATE TABLE reviews (movieID INTEGER NOT NULL, text TEXT DEFAULT 'None', rating INTEGER DEFAULT NULL NOT NULL, reviewID INTEGER PRIMARY KEY)",
+        )
+        .unwrap()
+        {
+            Statement::CreateTable(create_table) => {
+                assert_eq!(create_table.table_name, "reviews");
+                assert_eq!(create_table.columns.len(), 4);
+                assert!(!create_table.columns[0].primary_key);
+                assert!(create_table.columns[3].primary_key);
+                assert_eq!(create_table.columns[0].data_type, DataType::Integer);
+                assert!(create_table.columns[0].not_null);
+                assert!(create_table.columns[0].default.is_none());
+                assert_eq!(create_table.columns[1].data_type, DataType::Text);
+                assert!(!create_table.columns[1].not_null);
+                assert!(matches!(
+                    create_table.columns[1].default,
+                    Some(Const::String(_))
+                ));
+                assert!(create_table.columns[2].not_null);
+                assert!(matches!(create_table.columns[2].default, Some(Const::Null)));
+            }
+            _ => panic!("Expected CREATE TABLE statement"),
+        }
+        assert!(parse_statement("CREATE TABLE reviews (rating FLOAT)").is_err());
+        assert!(parse_statement("CREATE TABLE reviews ()").is_err());
+    }
+
+    /// Tests parsing of ATTACH statements and database-qualified names.
+    #[test]
+    fn test_parse_attach() {
+        match parse_statement("ATTACH 'database/music_data.json' AS music").unwrap() {
+            Statement::Attach(attach) => {
+                assert_eq!(attach.path, "database/music_data.json");
+                assert_eq!(attach.database_name, "music");
+            }
+            _ => panic!("Expected ATTACH statement"),
+        }
+        assert!(parse_statement("ATTACH music_data AS music").is_err());
+        assert!(parse_statement("ATTACH 'music_data.json'").is_err());
+
+        let query = parse_query(
+            "SELECT music.albums.title, movies.title FROM music.albums JOIN movies ON movies.id = music.albums.id WHERE music.albums.rating > 5",
+        ).unwrap();
+        assert_eq!(query.select[0].database.as_deref(), Some("music"));
+        assert_eq!(query.select[0].table_name, "albums");
+        assert_eq!(query.select[0].column_name, "title");
+        assert_eq!(query.select[0].qualified_name(), "music.albums.title");
+        assert_eq!(query.select[1].database, None);
+        assert_eq!(query.database.as_deref(), Some("music"));
+        assert_eq!(query.from, "albums");
+        assert_eq!(query.joins[0].database, None);
+        assert_eq!(query.joins[0].table_name, "movies");
+        assert!(matches!(
+            &query.joins[0].on.right,
+            Value::Column(column) if column.qualified_table() == "music.albums" && column.column_name == "id"
+        ));
+        assert!(matches!(
+            &query.where_clause.as_ref().unwrap().left,
+            Value::Column(column) if column.qualified_table() == "music.albums"
+        ));
+    }
+
+    /// Tests parsing of DROP TABLE and ALTER TABLE statements.
+    #[test]
+    fn test_parse_drop_and_alter_table() {
+        match parse_statement("DROP TABLE reviews").unwrap() {
+            Statement::DropTable(drop_table) => assert_eq!(drop_table.table_name, "reviews"),
+            _ => panic!("Expected DROP TABLE statement"),
+        }
+
+        match parse_statement("ALTER TABLE reviews ADD COLUMN stars INTEGER NOT NULL DEFAULT 3")
+            .unwrap()
+        {
+            Statement::AlterTable(AlterTable {
+                table_name,
+                action: AlterAction::Add(definition),
+            }) => {
+                assert_eq!(table_name, "reviews");
+                assert_eq!(definition.name, "stars");
+                assert!(definition.not_null);
+            }
+            _ => panic!("Expected ALTER TABLE ADD COLUMN statement"),
+        }
+        assert!(matches!(
+            parse_statement("ALTER TABLE reviews DROP COLUMN stars").unwrap(),
+            Statement::AlterTable(AlterTable {
+                action: AlterAction::Drop(_),
+                ..
+            })
+        ));
+        match parse_statement("ALTER TABLE reviews RENAME COLUMN stars TO rating").unwrap() {
+            Statement::AlterTable(AlterTable {
+                action: AlterAction::Rename { from, to },
+                ..
+            }) => assert_eq!((from.as_str(), to.as_str()), ("stars", "rating")),
+            _ => panic!("Expected ALTER TABLE RENAME COLUMN statement"),
+        }
+
+        assert!(parse_statement("DROP reviews").is_err());
+        assert!(parse_statement("ALTER TABLE reviews DROP stars").is_err());
+        assert!(parse_statement("ALTER TABLE reviews RENAME COLUMN stars rating").is_err());
+    }
 }
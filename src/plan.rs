@@ -0,0 +1,897 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::ops::Bound;
+use std::time::{Duration, Instant};
+
+use crate::database::{Database, Key, Row, Table};
+use crate::engine::{
+    compare_values, get_column_value, matches, provider_to_vec, row_to_map, table_to_vec,
+};
+use crate::parser::{Column, Comparison, Const, Join, OrderBy, Query, Value, ValueTest};
+use crate::provider::Filter;
+
+/// Represents a node in the operator tree the engine uses to execute a query.
+#[derive(Debug, Clone)]
+pub enum Plan {
+    /// Reads every row of a table.
+    Scan { table_name: String },
+    /// Reads the rows of a table that satisfy `condition` through an index.
+    ///
+    /// The left side of `condition` is the indexed column and the right side is a constant.
+    IndexScan {
+        table_name: String,
+        index: Access,
+        condition: ValueTest,
+    },
+    /// Reads the rows of a table provider that satisfy `condition`, which the provider applies
+    /// itself.
+    ///
+    /// The left side of `condition` is a column of the table and the right side is a constant.
+    FilteredScan {
+        table_name: String,
+        condition: ValueTest,
+    },
+    /// Combines every row of `left` with every row of `right` that satisfies `on`.
+    NestedLoopJoin {
+        left: Box<Plan>,
+        right: Box<Plan>,
+        on: ValueTest,
+    },
+    /// Combines every row of `left` with the rows of a table that satisfy the equality `on`,
+    /// found through an index on the table's side of `on`.
+    IndexJoin {
+        left: Box<Plan>,
+        table_name: String,
+        index: Access,
+        on: ValueTest,
+        /// The side of `on` that is evaluated against the rows of `left`.
+        outer: Value,
+    },
+    /// Keeps only the rows that satisfy `condition`.
+    Filter {
+        input: Box<Plan>,
+        condition: ValueTest,
+    },
+    /// Sorts the rows by the columns of `order_by`.
+    Sort {
+        input: Box<Plan>,
+        order_by: Vec<OrderBy>,
+    },
+    /// Keeps only the first `limit` rows.
+    Limit { input: Box<Plan>, limit: usize },
+    /// Keeps only the selected columns of each row.
+    Project {
+        input: Box<Plan>,
+        columns: Vec<Column>,
+    },
+}
+
+/// Represents an ordering of a table's rows that the engine can look rows up in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Access {
+    /// The order of the rows by ID, which the table always maintains.
+    PrimaryKey,
+    /// A secondary index, by name.
+    Index(String),
+}
+
+/// Represents the measurements collected while executing a plan node.
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    /// The number of rows produced by the node.
+    pub rows: usize,
+    /// The time spent in the node, including its inputs.
+    pub elapsed: Duration,
+    /// The measurements of the node's inputs, in the same order as `Plan::inputs`.
+    pub inputs: Vec<Analysis>,
+}
+
+impl Plan {
+    /// Builds the operator tree for a parsed SQL query.
+    ///
+    /// A `WHERE` clause comparing an indexed column or an ID with a constant is answered by an
+    /// index scan of that column's table, or by the provider of the table if it supports the
+    /// condition, and a join on an indexed column or an ID looks up the
+    /// joined rows through the index instead of scanning the whole table.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The parsed SQL query.
+    /// * `database` - The database the query will be executed on.
+    ///
+    /// # Returns
+    ///
+    /// The root node of the operator tree.
+    pub fn new(query: &Query, database: &Database) -> Plan {
+        // An index scan replaces the scan of a table the query reads, so it cannot answer a
+        // `WHERE` clause about another table; the clause is then kept as a filter.
+        let table_names: Vec<String> = std::iter::once(query.qualified_from())
+            .chain(query.joins.iter().map(Join::qualified_table))
+            .collect();
+        let index_scan = query
+            .where_clause
+            .as_ref()
+            .and_then(|where_clause| index_scan(where_clause, database))
+            .filter(|plan| match plan {
+                Plan::IndexScan { table_name, .. } | Plan::FilteredScan { table_name, .. } => {
+                    table_names.contains(table_name)
+                }
+                _ => false,
+            });
+        let access = |table_name: &String| match &index_scan {
+            Some(
+                plan @ (Plan::IndexScan { table_name: t, .. }
+                | Plan::FilteredScan { table_name: t, .. }),
+            ) if t == table_name => plan.clone(),
+            _ => Plan::Scan {
+                table_name: table_name.clone(),
+            },
+        };
+
+        let mut plan = access(&query.qualified_from());
+
+        for join in &query.joins {
+            let table_name = join.qualified_table();
+            let right = access(&table_name);
+            let index_join = match right {
+                Plan::Scan { .. } => index_join(&table_name, &join.on, database),
+                _ => None,
+            };
+
+            plan = match index_join {
+                Some((index, outer)) => Plan::IndexJoin {
+                    left: Box::new(plan),
+                    table_name,
+                    index,
+                    on: join.on.clone(),
+                    outer,
+                },
+                None => Plan::NestedLoopJoin {
+                    left: Box::new(plan),
+                    right: Box::new(right),
+                    on: join.on.clone(),
+                },
+            };
+        }
+
+        if let (Some(where_clause), None) = (&query.where_clause, &index_scan) {
+            plan = Plan::Filter {
+                input: Box::new(plan),
+                condition: where_clause.clone(),
+            };
+        }
+
+        if !query.order_by.is_empty() {
+            plan = Plan::Sort {
+                input: Box::new(plan),
+                order_by: query.order_by.clone(),
+            };
+        }
+
+        if let Some(limit) = query.limit {
+            plan = Plan::Limit {
+                input: Box::new(plan),
+                limit,
+            };
+        }
+
+        Plan::Project {
+            input: Box::new(plan),
+            columns: query.select.clone(),
+        }
+    }
+
+    /// Returns the inputs of the node.
+    pub fn inputs(&self) -> Vec<&Plan> {
+        match self {
+            Plan::Scan { .. } | Plan::IndexScan { .. } | Plan::FilteredScan { .. } => vec![],
+            Plan::NestedLoopJoin { left, right, .. } => vec![left, right],
+            Plan::IndexJoin { left, .. } => vec![left],
+            Plan::Filter { input, .. }
+            | Plan::Sort { input, .. }
+            | Plan::Limit { input, .. }
+            | Plan::Project { input, .. } => vec![input],
+        }
+    }
+
+    /// Executes the plan against a database.
+    ///
+    /// # Arguments
+    ///
+    /// * `database` - The database to read rows from.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the rows produced by the root node, or a message describing why
+    /// the rows of a table provider could not be read.
+    pub fn execute(&self, database: &Database) -> Result<Vec<BTreeMap<String, Value>>, String> {
+        Ok(self.analyze(database)?.0)
+    }
+
+    /// Executes the plan against a database, measuring every node.
+    ///
+    /// # Arguments
+    ///
+    /// * `database` - The database to read rows from.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the rows produced by the root node and the measurements of the
+    /// tree, or a message describing why the rows of a table provider could not be read.
+    pub fn analyze(
+        &self,
+        database: &Database,
+    ) -> Result<(Vec<BTreeMap<String, Value>>, Analysis), String> {
+        let start = Instant::now();
+
+        let (rows, inputs) = match self {
+            Plan::Scan { table_name } => (table_to_vec(database, table_name)?, vec![]),
+            Plan::IndexScan {
+                table_name,
+                index,
+                condition,
+            } => {
+                let table = database.table(table_name).unwrap();
+                let value = condition.right.get_const().unwrap();
+                let rows = lookup(table, index, &condition.comparison, &value)
+                    .into_iter()
+                    .map(|row| row_to_map(table_name, table.key_column(), row))
+                    .collect();
+                (rows, vec![])
+            }
+            Plan::FilteredScan {
+                table_name,
+                condition,
+            } => {
+                let provider = database.provider(table_name).unwrap();
+                let rows = provider_to_vec(table_name, provider, Some(&filter(condition)))?;
+                (rows, vec![])
+            }
+            Plan::NestedLoopJoin { left, right, on } => {
+                let (left_rows, left_analysis) = left.analyze(database)?;
+                let (right_rows, right_analysis) = right.analyze(database)?;
+                let rows = nested_loop_join(&left_rows, &right_rows, on);
+                (rows, vec![left_analysis, right_analysis])
+            }
+            Plan::IndexJoin {
+                left,
+                table_name,
+                index,
+                outer,
+                ..
+            } => {
+                let (left_rows, left_analysis) = left.analyze(database)?;
+                let table = database.table(table_name).unwrap();
+                let mut rows = vec![];
+
+                for row in left_rows {
+                    let Some(value) = get_column_value(&row, outer) else {
+                        continue;
+                    };
+                    for join_row in lookup(table, index, &Comparison::Eq, &value) {
+                        let mut new_row = row.clone();
+                        new_row.extend(row_to_map(table_name, table.key_column(), join_row));
+                        rows.push(new_row);
+                    }
+                }
+                (rows, vec![left_analysis])
+            }
+            Plan::Filter { input, condition } => {
+                let (mut rows, analysis) = input.analyze(database)?;
+                rows.retain(|row| matches(row, condition));
+                (rows, vec![analysis])
+            }
+            Plan::Sort { input, order_by } => {
+                let (mut rows, analysis) = input.analyze(database)?;
+                rows.sort_by(|a, b| compare_rows(a, b, order_by));
+                (rows, vec![analysis])
+            }
+            Plan::Limit { input, limit } => {
+                let (mut rows, analysis) = input.analyze(database)?;
+                rows.truncate(*limit);
+                (rows, vec![analysis])
+            }
+            Plan::Project { input, columns } => {
+                let (rows, analysis) = input.analyze(database)?;
+                (project(rows, columns), vec![analysis])
+            }
+        };
+
+        let analysis = Analysis {
+            rows: rows.len(),
+            elapsed: start.elapsed(),
+            inputs,
+        };
+        Ok((rows, analysis))
+    }
+
+    /// Renders the operator tree, optionally annotated with measurements.
+    ///
+    /// # Arguments
+    ///
+    /// * `analysis` - The measurements collected by `Plan::analyze`, if any.
+    ///
+    /// # Returns
+    ///
+    /// The rendered tree, one node per line.
+    pub fn explain(&self, analysis: Option<&Analysis>) -> String {
+        let mut output = String::new();
+        self.write_tree(&mut output, 0, analysis);
+        if let Some(analysis) = analysis {
+            output.push_str(&format!(
+                "Execution time: {:.3} ms\n",
+                analysis.elapsed.as_secs_f64() * 1000.0
+            ));
+        }
+        output
+    }
+
+    /// Writes the node and its inputs to `output`, indented by `depth`.
+    fn write_tree(&self, output: &mut String, depth: usize, analysis: Option<&Analysis>) {
+        if depth > 0 {
+            output.push_str(&"   ".repeat(depth - 1));
+            output.push_str("-> ");
+        }
+        output.push_str(&self.to_string());
+        if let Some(analysis) = analysis {
+            output.push_str(&format!(
+                " (rows={} time={:.3} ms)",
+                analysis.rows,
+                analysis.elapsed.as_secs_f64() * 1000.0
+            ));
+        }
+        output.push('\n');
+
+        for (i, input) in self.inputs().into_iter().enumerate() {
+            input.write_tree(output, depth + 1, analysis.map(|a| &a.inputs[i]));
+        }
+    }
+}
+
+impl fmt::Display for Plan {
+    /// Formats a single node of the operator tree, without its inputs.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Plan::Scan { table_name } => write!(f, "Scan {}", table_name),
+            Plan::IndexScan {
+                table_name,
+                index,
+                condition,
+            } => write!(
+                f,
+                "Index Scan {} using {} where {}",
+                table_name,
+                index,
+                explain_test(condition)
+            ),
+            Plan::FilteredScan {
+                table_name,
+                condition,
+            } => write!(
+                f,
+                "Filtered Scan {} where {}",
+                table_name,
+                explain_test(condition)
+            ),
+            Plan::NestedLoopJoin { on, .. } => {
+                write!(f, "Nested Loop Join on {}", explain_test(on))
+            }
+            Plan::IndexJoin {
+                table_name,
+                index,
+                on,
+                ..
+            } => write!(
+                f,
+                "Index Join {} using {} on {}",
+                table_name,
+                index,
+                explain_test(on)
+            ),
+            Plan::Filter { condition, .. } => write!(f, "Filter {}", explain_test(condition)),
+            Plan::Sort { order_by, .. } => {
+                let columns: Vec<String> = order_by
+                    .iter()
+                    .map(|o| {
+                        let direction = if o.descending { " DESC" } else { "" };
+                        format!("{}{}", o.column.qualified_name(), direction)
+                    })
+                    .collect();
+                write!(f, "Sort {}", columns.join(", "))
+            }
+            Plan::Limit { limit, .. } => write!(f, "Limit {}", limit),
+            Plan::Project { columns, .. } => {
+                let columns: Vec<String> = columns.iter().map(Column::qualified_name).collect();
+                write!(f, "Project {}", columns.join(", "))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Access {
+    /// Formats the name of the index.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Access::PrimaryKey => write!(f, "primary key"),
+            Access::Index(index_name) => write!(f, "{}", index_name),
+        }
+    }
+}
+
+/// Formats a value test for display in a plan, quoting string constants.
+fn explain_test(test: &ValueTest) -> String {
+    let explain_value = |value: &Value| match value {
+        Value::Const(Const::String(s)) => format!("'{}'", s),
+        value => value.to_string(),
+    };
+    format!(
+        "{} {} {}",
+        explain_value(&test.left),
+        test.comparison,
+        explain_value(&test.right)
+    )
+}
+
+/// Builds an index scan answering a `WHERE` clause, if it compares an indexed column with a constant,
+/// or a filtered scan if it compares a column of a table provider that supports the condition.
+///
+/// # Arguments
+///
+/// * `where_clause` - The condition of the `WHERE` clause.
+/// * `database` - The database the query will be executed on.
+///
+/// # Returns
+///
+/// An `Option` containing an `IndexScan` or `FilteredScan` node, or `None` if neither an index
+/// nor a provider can answer the condition.
+fn index_scan(where_clause: &ValueTest, database: &Database) -> Option<Plan> {
+    let condition = match (&where_clause.left, &where_clause.right) {
+        (Value::Column(_), Value::Const(_)) => where_clause.clone(),
+        (Value::Const(_), Value::Column(_)) => ValueTest {
+            left: where_clause.right.clone(),
+            comparison: flip(&where_clause.comparison),
+            right: where_clause.left.clone(),
+        },
+        _ => return None,
+    };
+    let Value::Column(column) = &condition.left else {
+        return None;
+    };
+
+    let table_name = column.qualified_table();
+    let Some(table) = database.table(&table_name) else {
+        let provider = database.provider(&table_name)?;
+        return provider
+            .supports_filter(&filter(&condition))
+            .then_some(Plan::FilteredScan {
+                table_name,
+                condition,
+            });
+    };
+
+    if condition.comparison == Comparison::Ne {
+        return None;
+    }
+    let index = access(table, &column.column_name)?;
+
+    Some(Plan::IndexScan {
+        table_name,
+        index,
+        condition,
+    })
+}
+
+/// Converts a condition comparing a column with a constant to the filter given to a provider.
+fn filter(condition: &ValueTest) -> Filter {
+    Filter {
+        column: condition.left.get_column_name().to_string(),
+        comparison: condition.comparison.clone(),
+        value: condition.right.get_const().unwrap(),
+    }
+}
+
+/// Finds an index that can look up the rows of a joined table.
+///
+/// # Arguments
+///
+/// * `table_name` - The name of the joined table.
+/// * `on` - The condition of the join.
+/// * `database` - The database the query will be executed on.
+///
+/// # Returns
+///
+/// An `Option` containing the index and the side of `on` evaluated against the rows
+/// already joined, or `None` if no index can be used.
+fn index_join(table_name: &str, on: &ValueTest, database: &Database) -> Option<(Access, Value)> {
+    if on.comparison != Comparison::Eq {
+        return None;
+    }
+
+    let table = database.table(table_name)?;
+    let (inner, outer) = match (&on.left, &on.right) {
+        (Value::Column(_), Value::Column(right)) if right.qualified_table() == table_name => {
+            (right, &on.left)
+        }
+        (Value::Column(left), Value::Column(_)) if left.qualified_table() == table_name => {
+            (left, &on.right)
+        }
+        _ => return None,
+    };
+    if outer.get_table_name() == table_name {
+        return None;
+    }
+
+    let index = access(table, &inner.column_name)?;
+    Some((index, outer.clone()))
+}
+
+/// Finds an index over a column of a table, preferring the ID ordering of the rows for
+/// the primary-key column.
+///
+/// # Arguments
+///
+/// * `table` - The table.
+/// * `column_name` - The name of the column.
+///
+/// # Returns
+///
+/// An `Option` containing the index, or `None` if the column is not indexed.
+fn access(table: &Table, column_name: &str) -> Option<Access> {
+    if column_name == table.key_column() {
+        return Some(Access::PrimaryKey);
+    }
+    table
+        .index_on(column_name)
+        .map(|index_name| Access::Index(index_name.to_string()))
+}
+
+/// Looks up the rows of a table whose indexed column satisfies `column <comparison> value`.
+///
+/// # Arguments
+///
+/// * `table` - The table.
+/// * `index` - The index over the column.
+/// * `comparison` - The comparison operator.
+/// * `value` - The constant the column is compared with.
+///
+/// # Returns
+///
+/// The matching rows, in ID order.
+fn lookup<'t>(
+    table: &'t Table,
+    index: &Access,
+    comparison: &Comparison,
+    value: &Const,
+) -> Vec<&'t Row> {
+    match index {
+        Access::PrimaryKey => match value {
+            Const::Number(n) => table.range(id_range(comparison, *n)).collect(),
+            Const::String(_) | Const::Null => vec![],
+        },
+        Access::Index(index_name) => match key(value) {
+            Some(key) => {
+                let mut rows = table.index_range(index_name, key_range(comparison, key));
+                rows.sort();
+                rows
+            }
+            None => vec![],
+        },
+    }
+}
+
+/// Returns the range of row IDs that satisfy `id <comparison> id`.
+fn id_range(comparison: &Comparison, id: i64) -> (Bound<i64>, Bound<i64>) {
+    match comparison {
+        Comparison::Eq => (Bound::Included(id), Bound::Included(id)),
+        Comparison::Gt => (Bound::Excluded(id), Bound::Unbounded),
+        Comparison::Ge => (Bound::Included(id), Bound::Unbounded),
+        Comparison::Lt => (Bound::Unbounded, Bound::Excluded(id)),
+        Comparison::Le => (Bound::Unbounded, Bound::Included(id)),
+        Comparison::Ne => unreachable!("<> is never answered through an index"),
+    }
+}
+
+/// Returns the comparison that holds when the operands of `comparison` are swapped.
+fn flip(comparison: &Comparison) -> Comparison {
+    match comparison {
+        Comparison::Gt => Comparison::Lt,
+        Comparison::Lt => Comparison::Gt,
+        Comparison::Le => Comparison::Ge,
+        Comparison::Ge => Comparison::Le,
+        comparison => comparison.clone(),
+    }
+}
+
+/// Converts a constant to an index key.
+///
+/// # Returns
+///
+/// An `Option` containing the key, or `None` for `NULL`, which no indexed value equals.
+fn key(value: &Const) -> Option<Key> {
+    match value {
+        Const::Number(n) => Some(Key::Number(*n)),
+        Const::String(s) => Some(Key::String(s.clone())),
+        Const::Null => None,
+    }
+}
+
+/// Returns the range of index keys that satisfy `key_column <comparison> key`.
+///
+/// The range never crosses from numbers into strings, matching `compare_values`,
+/// which only compares values of the same kind.
+fn key_range(comparison: &Comparison, key: Key) -> (Bound<Key>, Bound<Key>) {
+    let (min, max) = match key {
+        Key::Number(_) => (
+            Bound::Unbounded,
+            Bound::Excluded(Key::String(String::new())),
+        ),
+        Key::String(_) => (
+            Bound::Included(Key::String(String::new())),
+            Bound::Unbounded,
+        ),
+    };
+
+    match comparison {
+        Comparison::Eq => (Bound::Included(key.clone()), Bound::Included(key)),
+        Comparison::Gt => (Bound::Excluded(key), max),
+        Comparison::Ge => (Bound::Included(key), max),
+        Comparison::Lt => (min, Bound::Excluded(key)),
+        Comparison::Le => (min, Bound::Included(key)),
+        Comparison::Ne => unreachable!("<> is never answered through an index"),
+    }
+}
+
+/// Joins two sets of rows, keeping every combination that satisfies `on`.
+fn nested_loop_join(
+    left_rows: &[BTreeMap<String, Value>],
+    right_rows: &[BTreeMap<String, Value>],
+    on: &ValueTest,
+) -> Vec<BTreeMap<String, Value>> {
+    let mut rows = vec![];
+
+    for row in left_rows {
+        for join_row in right_rows {
+            let left_value =
+                get_column_value(row, &on.left).or(get_column_value(join_row, &on.left));
+            let right_value =
+                get_column_value(row, &on.right).or(get_column_value(join_row, &on.right));
+
+            // Like a WHERE clause, a condition on a missing column is not satisfied.
+            let (Some(left_value), Some(right_value)) = (left_value, right_value) else {
+                continue;
+            };
+            if compare_values(&left_value, &on.comparison, &right_value) {
+                let mut new_row = row.clone();
+                for (k, v) in join_row {
+                    new_row.insert(k.clone(), v.clone());
+                }
+                rows.push(new_row);
+            }
+        }
+    }
+
+    rows
+}
+
+/// Compares two rows by the columns of an ORDER BY clause.
+///
+/// `NULL`s and missing values come before numbers, which come before strings, and a descending
+/// column reverses the order.
+fn compare_rows(
+    a: &BTreeMap<String, Value>,
+    b: &BTreeMap<String, Value>,
+    order_by: &[OrderBy],
+) -> Ordering {
+    let rank = |value: &Option<Const>| match value {
+        None | Some(Const::Null) => 0,
+        Some(Const::Number(_)) => 1,
+        Some(Const::String(_)) => 2,
+    };
+
+    for o in order_by {
+        let column = Value::Column(o.column.clone());
+        let a = get_column_value(a, &column);
+        let b = get_column_value(b, &column);
+        let ordering = match (&a, &b) {
+            (Some(Const::Number(a)), Some(Const::Number(b))) => a.cmp(b),
+            (Some(Const::String(a)), Some(Const::String(b))) => a.cmp(b),
+            _ => rank(&a).cmp(&rank(&b)),
+        };
+        let ordering = if o.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Keeps only the selected columns of each row.
+fn project(rows: Vec<BTreeMap<String, Value>>, columns: &[Column]) -> Vec<BTreeMap<String, Value>> {
+    let column_names: BTreeSet<String> = columns.iter().map(Column::qualified_name).collect();
+
+    rows.into_iter()
+        .map(|x| {
+            x.into_iter()
+                .filter(|(k, _)| column_names.contains(k))
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{database, parser};
+
+    #[test]
+    fn test_explain() {
+        let query = parser::parse_query(
+            "SELECT movies.title FROM movies JOIN actors_in_movies ON actors_in_movies.movieID = movies.id WHERE movies.title = 'Alien'",
+        ).unwrap();
+        let db = database::load_database("database/test_data.json").unwrap();
+        let plan = Plan::new(&query, &db);
+
+        assert_eq!(
+            plan.explain(None),
+            "Project movies.title\n\
+             -> Filter movies.title = 'Alien'\n   \
+             -> Nested Loop Join on actors_in_movies.movieID = movies.id\n      \
+             -> Scan movies\n      \
+             -> Scan actors_in_movies\n"
+        );
+    }
+
+    /// Tests that a `WHERE` clause about a table the query does not read is not dropped.
+    #[test]
+    fn test_filter_on_other_table() {
+        let query =
+            parser::parse_query("SELECT movies.title FROM movies WHERE actors.id = 1").unwrap();
+        let db = database::load_database("database/test_data.json").unwrap();
+        let plan = Plan::new(&query, &db);
+
+        assert_eq!(
+            plan.explain(None),
+            "Project movies.title\n\
+             -> Filter actors.id = 1\n   \
+             -> Scan movies\n"
+        );
+    }
+
+    #[test]
+    fn test_analyze() {
+        let db = database::load_database("database/test_data.json").unwrap();
+        let query = parser::parse_query(
+            "SELECT movies.title FROM movies JOIN actors_in_movies ON actors_in_movies.movieID = movies.id WHERE movies.cert <= 15",
+        ).unwrap();
+        let plan = Plan::new(&query, &db);
+
+        let (rows, analysis) = plan.analyze(&db).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(analysis.rows, 3);
+
+        let join = &analysis.inputs[0].inputs[0];
+        assert_eq!(join.rows, 4);
+        assert_eq!(join.inputs[0].rows, 3);
+        assert_eq!(join.inputs[1].rows, 4);
+        assert!(plan
+            .explain(Some(&analysis))
+            .contains("Scan movies (rows=3 "));
+    }
+
+    #[test]
+    fn test_sort_and_limit() {
+        let db = database::load_database("database/test_data.json").unwrap();
+        let query = parser::parse_query(
+            "SELECT movies.title FROM movies WHERE movies.cert >= 12 ORDER BY movies.cert DESC LIMIT 2",
+        ).unwrap();
+        let plan = Plan::new(&query, &db);
+
+        assert_eq!(
+            plan.explain(None),
+            "Project movies.title\n\
+             -> Limit 2\n   \
+             -> Sort movies.cert DESC\n      \
+             -> Filter movies.cert >= 12\n         \
+             -> Scan movies\n"
+        );
+
+        let (rows, analysis) = plan.analyze(&db).unwrap();
+        let titles: Vec<_> = rows
+            .iter()
+            .map(|row| row["movies.title"].to_string())
+            .collect();
+        assert_eq!(titles, ["Alien", "The Matrix"]);
+        assert_eq!(analysis.inputs[0].rows, 2);
+        assert_eq!(analysis.inputs[0].inputs[0].rows, 3);
+    }
+
+    #[test]
+    fn test_index_scan() {
+        let mut db = database::load_database("database/test_data.json").unwrap();
+        db.create_index("idx_cert", "movies", "cert").unwrap();
+
+        let query = parser::parse_query(
+            "SELECT movies.title, actors_in_movies.actorID FROM movies JOIN actors_in_movies ON actors_in_movies.movieID = movies.id WHERE 15 >= movies.cert",
+        ).unwrap();
+        let plan = Plan::new(&query, &db);
+
+        assert_eq!(
+            plan.explain(None),
+            "Project movies.title, actors_in_movies.actorID\n\
+             -> Nested Loop Join on actors_in_movies.movieID = movies.id\n   \
+             -> Index Scan movies using idx_cert where movies.cert <= 15\n   \
+             -> Scan actors_in_movies\n"
+        );
+        assert_eq!(plan.execute(&db).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_index_join() {
+        let mut db = database::load_database("database/test_data.json").unwrap();
+        db.create_index("idx_movie", "actors_in_movies", "movieID")
+            .unwrap();
+        db.create_index("idx_title", "movies", "title").unwrap();
+
+        let query = parser::parse_query(
+            "SELECT movies.title, actors_in_movies.actorID FROM movies JOIN actors_in_movies ON actors_in_movies.movieID = movies.id WHERE movies.title = 'The Matrix'",
+        ).unwrap();
+        let plan = Plan::new(&query, &db);
+
+        assert_eq!(
+            plan.explain(None),
+            "Project movies.title, actors_in_movies.actorID\n\
+             -> Index Join actors_in_movies using idx_movie on actors_in_movies.movieID = movies.id\n   \
+             -> Index Scan movies using idx_title where movies.title = 'The Matrix'\n"
+        );
+
+        let rows = plan.execute(&db).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert!(rows
+            .iter()
+            .all(|row| row["movies.title"].to_string() == "The Matrix"));
+    }
+
+    #[test]
+    fn test_primary_key() {
+        let db = database::load_database("database/test_data.json").unwrap();
+
+        let query = parser::parse_query(
+            "SELECT movies.title, actors.name FROM movies JOIN actors_in_movies ON actors_in_movies.movieID = movies.id JOIN actors ON actors_in_movies.actorID = actors.id WHERE movies.id = 1",
+        ).unwrap();
+        let plan = Plan::new(&query, &db);
+
+        assert_eq!(
+            plan.explain(None),
+            "Project movies.title, actors.name\n\
+             -> Index Join actors using primary key on actors_in_movies.actorID = actors.id\n   \
+             -> Nested Loop Join on actors_in_movies.movieID = movies.id\n      \
+             -> Index Scan movies using primary key where movies.id = 1\n      \
+             -> Scan actors_in_movies\n"
+        );
+        assert_eq!(plan.execute(&db).unwrap().len(), 2);
+
+        for (condition, expected) in [
+            ("movies.id > 1", 2),
+            ("movies.id <= 2", 2),
+            ("movies.id < 0", 0),
+            ("movies.id = 'Alien'", 0),
+        ] {
+            let sql = format!("SELECT movies.title FROM movies WHERE {}", condition);
+            let query = parser::parse_query(&sql).unwrap();
+            let plan = Plan::new(&query, &db);
+            assert!(matches!(
+                plan.inputs()[0],
+                Plan::IndexScan {
+                    index: Access::PrimaryKey,
+                    ..
+                }
+            ));
+            assert_eq!(plan.execute(&db).unwrap().len(), expected, "{}", condition);
+        }
+    }
+}
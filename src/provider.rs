@@ -0,0 +1,352 @@
+use std::fmt;
+
+use crate::database::{Row, Schema};
+use crate::engine::compare_values;
+use crate::parser::{Comparison, Const};
+
+/// Represents a source of rows that can be registered as a read-only table of a database, such
+/// as a collection held in memory, generated data or a file in a custom format.
+///
+/// A registered table is queried and joined like a table loaded from JSON. Its rows are read
+/// again each time a query scans the table, and it cannot be changed by SQL statements.
+/// Providers are `Send` and `Sync`, so a database holding them can be shared between threads.
+pub trait TableProvider: fmt::Debug + Send + Sync {
+    /// Describes the columns of the rows. The primary-key column holds the ID of each row.
+    fn schema(&self) -> Schema;
+
+    /// Reads the rows of the table.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - A condition the rows must satisfy, which is only given if `supports_filter`
+    ///   accepted it. Otherwise every row is read and the engine filters them.
+    ///
+    /// # Returns
+    ///
+    /// The rows, each with a unique ID. They are checked against `schema` as they are read, and
+    /// a query reading a row that does not match it, or that repeats an ID, fails.
+    fn scan(&self, filter: Option<&Filter>) -> Vec<Row>;
+
+    /// Checks whether the provider applies a condition itself when scanning its rows, so the
+    /// engine does not need to read the rows that fail it.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - The condition of the `WHERE` clause of a query.
+    ///
+    /// # Returns
+    ///
+    /// `true` if `scan` returns exactly the rows satisfying the condition. The default is `false`.
+    fn supports_filter(&self, _filter: &Filter) -> bool {
+        false
+    }
+}
+
+/// Represents a condition on a column of a table provider, which compares the column with a
+/// constant (`column <comparison> value`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    /// The name of the column, not qualified by the table.
+    pub column: String,
+    pub comparison: Comparison,
+    pub value: Const,
+}
+
+impl Filter {
+    /// Checks whether a column value satisfies the condition, as the engine would check it.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value of the column in a row.
+    ///
+    /// # Returns
+    ///
+    /// A boolean indicating whether the value satisfies the condition. `NULL` and values the
+    /// engine cannot compare never do.
+    pub fn matches(&self, value: &serde_json::Value) -> bool {
+        let value = match value {
+            serde_json::Value::Number(n) => n.as_i64().map(Const::Number),
+            serde_json::Value::String(s) => Some(Const::String(s.clone())),
+            _ => None,
+        };
+        value.is_some_and(|value| compare_values(&value, &self.comparison, &self.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use crate::database::{ColumnDef, DataType, Database, LoadMode};
+    use crate::engine::Outcome;
+    use crate::{Connection, Error};
+
+    /// Generates the numbers from 1 to `count` with their squares, applying conditions on the
+    /// numbers itself.
+    #[derive(Debug)]
+    struct Numbers {
+        count: i64,
+    }
+
+    impl TableProvider for Numbers {
+        fn schema(&self) -> Schema {
+            Schema {
+                columns: vec![ColumnDef {
+                    name: "square".to_string(),
+                    data_type: DataType::Integer,
+                    not_null: true,
+                    default: None,
+                }],
+                primary_key: Some("n".to_string()),
+                open: false,
+            }
+        }
+
+        fn scan(&self, filter: Option<&Filter>) -> Vec<Row> {
+            (1..=self.count)
+                .filter(|n| filter.is_none_or(|filter| filter.matches(&(*n).into())))
+                .map(|n| Row {
+                    id: n,
+                    columns: BTreeMap::from([("square".to_string(), (n * n).into())]),
+                })
+                .collect()
+        }
+
+        fn supports_filter(&self, filter: &Filter) -> bool {
+            filter.column == "n"
+        }
+    }
+
+    /// Holds the ratings of movies in memory.
+    #[derive(Debug)]
+    struct Ratings(Vec<(i64, &'static str)>);
+
+    impl TableProvider for Ratings {
+        fn schema(&self) -> Schema {
+            Schema {
+                columns: vec![
+                    ColumnDef {
+                        name: "movieID".to_string(),
+                        data_type: DataType::Integer,
+                        not_null: true,
+                        default: None,
+                    },
+                    ColumnDef {
+                        name: "stars".to_string(),
+                        data_type: DataType::Text,
+                        not_null: false,
+                        default: None,
+                    },
+                ],
+                primary_key: None,
+                open: false,
+            }
+        }
+
+        fn scan(&self, _filter: Option<&Filter>) -> Vec<Row> {
+            self.0
+                .iter()
+                .zip(1..)
+                .map(|((movie_id, stars), id)| Row {
+                    id,
+                    columns: BTreeMap::from([
+                        ("movieID".to_string(), (*movie_id).into()),
+                        ("stars".to_string(), (*stars).into()),
+                    ]),
+                })
+                .collect()
+        }
+    }
+
+    /// Returns the rows it is given, under the schema of `Ratings`.
+    #[derive(Debug)]
+    struct Given(Vec<serde_json::Value>);
+
+    impl TableProvider for Given {
+        fn schema(&self) -> Schema {
+            Ratings(vec![]).schema()
+        }
+
+        fn scan(&self, _filter: Option<&Filter>) -> Vec<Row> {
+            self.0
+                .iter()
+                .zip(1..)
+                .map(|(columns, id)| Row {
+                    id,
+                    columns: serde_json::from_value(columns.clone()).unwrap(),
+                })
+                .collect()
+        }
+    }
+
+    /// Returns the same rating twice, under the same ID.
+    #[derive(Debug)]
+    struct Repeated;
+
+    impl TableProvider for Repeated {
+        fn schema(&self) -> Schema {
+            Ratings(vec![]).schema()
+        }
+
+        fn scan(&self, filter: Option<&Filter>) -> Vec<Row> {
+            let ratings = Ratings(vec![(1, "***")]);
+            let mut rows = ratings.scan(filter);
+            rows.extend(ratings.scan(filter));
+            rows
+        }
+    }
+
+    #[test]
+    fn test_providers() {
+        let mut connection = Connection::open("database/test_data.json").unwrap();
+        connection
+            .register_table("numbers", Numbers { count: 100 })
+            .unwrap();
+        connection
+            .register_table(
+                "ratings",
+                Ratings(vec![(1, "****"), (3, "*****"), (1, "***")]),
+            )
+            .unwrap();
+
+        let rows = connection
+            .query("SELECT numbers.n, numbers.square FROM numbers WHERE 3 >= numbers.n")
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows.get(2).unwrap().get::<i64>(1).unwrap(), 9);
+        assert_eq!(rows.columns()[1].data_type, Some(DataType::Integer));
+
+        let Outcome::Plan(plan) = connection
+            .execute("EXPLAIN ANALYZE SELECT numbers.n FROM numbers WHERE numbers.n <> 1")
+            .unwrap()
+        else {
+            panic!("Expected a plan");
+        };
+        assert!(plan.contains("-> Filtered Scan numbers where numbers.n <> 1 (rows=99 "));
+
+        let Outcome::Plan(plan) = connection
+            .execute("EXPLAIN SELECT numbers.n FROM numbers WHERE numbers.square > 50")
+            .unwrap()
+        else {
+            panic!("Expected a plan");
+        };
+        assert!(plan.contains("-> Filter numbers.square > 50\n   -> Scan numbers\n"));
+
+        let rows = connection
+            .query(
+                "SELECT movies.title, ratings.stars, numbers.square FROM movies \
+                 JOIN ratings ON ratings.movieID = movies.id \
+                 JOIN numbers ON numbers.n = ratings.id \
+                 WHERE ratings.stars <> '***' ORDER BY movies.title",
+            )
+            .unwrap();
+        let rows: Vec<(String, String, i64)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.get(0).unwrap(),
+                    row.get(1).unwrap(),
+                    row.get(2).unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("Alien".to_string(), "*****".to_string(), 4),
+                ("The Matrix".to_string(), "****".to_string(), 1),
+            ]
+        );
+
+        assert!(matches!(
+            connection.register_table("movies", Numbers { count: 1 }),
+            Err(Error::Execution(_))
+        ));
+        for sql in [
+            "INSERT INTO numbers (square) VALUES (4)",
+            "UPDATE numbers SET square = 1",
+            "DELETE FROM ratings",
+            "DROP TABLE ratings",
+            "ALTER TABLE numbers DROP COLUMN square",
+            "CREATE INDEX idx_square ON numbers(square)",
+            "CREATE TABLE numbers (n INTEGER)",
+        ] {
+            assert!(connection.execute(sql).is_err(), "{}", sql);
+        }
+        assert_eq!(
+            connection
+                .query("SELECT numbers.n FROM numbers")
+                .unwrap()
+                .len(),
+            100
+        );
+    }
+
+    /// Tests that the rows of a provider are checked against its schema, and its name against
+    /// the names SQL can refer to.
+    #[test]
+    fn test_invalid_providers() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Connection>();
+
+        // A provider and an NDJSON table cannot share a name.
+        let mut db = Database::new();
+        db.register_table("events", Given(vec![])).unwrap();
+        let error = db
+            .load_ndjson_table("events", "database/events.ndjson", LoadMode::Lenient)
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+        assert!(db.tables.is_empty());
+
+        let mut connection = Connection::open("database/test_data.json").unwrap();
+        for name in ["", "my ratings", "main.ratings"] {
+            assert!(
+                connection.register_table(name, Given(vec![])).is_err(),
+                "{}",
+                name
+            );
+        }
+
+        for (row, error) in [
+            (
+                serde_json::json!({"movieID": 2.5}),
+                "Table given row 1: Column movieID expects INTEGER but got 2.5",
+            ),
+            (
+                serde_json::json!({"stars": "*"}),
+                "Table given row 1: Column movieID cannot be null",
+            ),
+            (
+                serde_json::json!({"movieID": 1, "director": "Scott"}),
+                "Table given row 1: Column director is not defined",
+            ),
+        ] {
+            let mut connection = Connection::open("database/test_data.json").unwrap();
+            connection
+                .register_table("given", Given(vec![row]))
+                .unwrap();
+            match connection.query("SELECT given.movieID FROM given") {
+                Err(Error::Execution(message)) => assert_eq!(message, error),
+                other => panic!("Expected an error, got {:?}", other),
+            }
+        }
+
+        let mut connection = Connection::open("database/test_data.json").unwrap();
+        connection.register_table("repeated", Repeated).unwrap();
+        match connection.query("SELECT repeated.movieID FROM repeated") {
+            Err(Error::Execution(message)) => {
+                assert_eq!(message, "Table repeated row 1: Row 1 already exists")
+            }
+            other => panic!("Expected an error, got {:?}", other),
+        }
+
+        connection
+            .register_table("given", Given(vec![serde_json::json!({"movieID": 1})]))
+            .unwrap();
+        let rows = connection
+            .query("SELECT given.movieID, given.stars FROM given")
+            .unwrap();
+        assert_eq!(rows.get(0).unwrap().get::<Option<String>>(1).unwrap(), None);
+    }
+}
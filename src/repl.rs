@@ -0,0 +1,536 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use serde_json::Value;
+
+use simple_sql_engine::database::{Database, Schema};
+use simple_sql_engine::output::Format;
+use simple_sql_engine::Connection;
+
+use crate::{open_database, print_outcome};
+
+/// The name of the file, in the home directory, that the shell keeps its history in.
+const HISTORY_FILE: &str = ".simple_sql_history";
+
+/// The greatest number of entries the history keeps, in memory and in its file. Older entries
+/// are dropped.
+const MAX_HISTORY: usize = 1000;
+
+const HELP: &str = "\
+Statements end with ';' and may span several lines.
+
+.tables                List the tables
+.schema [TABLE]        Show the definition of a table, or of every table
+.open PATH             Load another database in place of the current one
+.mode [FORMAT]         Show or set the output format, as with --format
+.timer on|off          Show the time each statement takes
+.history               List the statements and commands entered so far
+.help                  Show this help
+.quit, .exit           Leave the shell
+";
+
+/// Represents a command of the shell that is not SQL, written with a leading `.`.
+#[derive(Debug, PartialEq)]
+enum MetaCommand {
+    Tables,
+    Schema(Option<String>),
+    Open(String),
+    Mode(Option<Format>),
+    Timer(bool),
+    History,
+    Help,
+    Quit,
+}
+
+impl MetaCommand {
+    /// Parses a meta-command.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line holding the command, starting with `.`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the command, or a message describing why it is invalid.
+    fn parse(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let argument = words.next();
+        if words.next().is_some() {
+            return Err(format!("Too many arguments to {}", name));
+        }
+
+        match (name, argument) {
+            (".tables", None) => Ok(MetaCommand::Tables),
+            (".schema", table_name) => Ok(MetaCommand::Schema(table_name.map(str::to_string))),
+            (".open", Some(path)) => Ok(MetaCommand::Open(path.to_string())),
+            (".open", None) => Err("Usage: .open PATH".to_string()),
+            (".mode", None) => Ok(MetaCommand::Mode(None)),
+            (".mode", Some(format)) => Ok(MetaCommand::Mode(Some(format.parse()?))),
+            (".timer", Some("on")) => Ok(MetaCommand::Timer(true)),
+            (".timer", Some("off")) => Ok(MetaCommand::Timer(false)),
+            (".timer", _) => Err("Usage: .timer on|off".to_string()),
+            (".history", None) => Ok(MetaCommand::History),
+            (".help", None) => Ok(MetaCommand::Help),
+            (".quit" | ".exit", None) => Ok(MetaCommand::Quit),
+            (".tables" | ".history" | ".help" | ".quit" | ".exit", Some(_)) => {
+                Err(format!("{} takes no arguments", name))
+            }
+            _ => Err(format!("Unknown command {}, see .help", name)),
+        }
+    }
+}
+
+/// Represents an interactive shell, which runs statements against a database that stays loaded
+/// between them.
+pub struct Repl {
+    connection: Connection,
+    strict: bool,
+    discard_malformed: bool,
+    format: Format,
+    timer: bool,
+    /// The statements and commands entered so far, oldest first.
+    history: Vec<String>,
+    /// The file the history is kept in across sessions, if any.
+    history_path: Option<PathBuf>,
+}
+
+impl Repl {
+    /// Creates a shell over a loaded database, with the history kept in the home directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection` - The connection to the database to run statements against.
+    /// * `strict` - Whether databases opened with `.open` fail on malformed tables or rows.
+    /// * `discard_malformed` - Whether databases opened with `.open` may be changed when
+    ///   malformed tables or rows were skipped.
+    /// * `format` - How query results are printed, until changed with `.mode`.
+    pub fn new(
+        connection: Connection,
+        strict: bool,
+        discard_malformed: bool,
+        format: Format,
+    ) -> Self {
+        let history_path = env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+        let history = history_path
+            .as_deref()
+            .map(read_history)
+            .unwrap_or_default();
+
+        Repl {
+            connection,
+            strict,
+            discard_malformed,
+            format,
+            timer: false,
+            history,
+            history_path,
+        }
+    }
+
+    /// Reads statements and meta-commands until the input ends or `.quit` is entered.
+    ///
+    /// A statement may span several lines and runs once a line ends it with `;`. Errors are
+    /// printed and do not end the shell.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input to read lines from.
+    /// * `out` - The writer to print prompts, results and errors to.
+    /// * `prompt` - Whether to print a prompt before each line.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or an I/O error.
+    pub fn run<R: BufRead, W: Write>(
+        &mut self,
+        mut input: R,
+        out: &mut W,
+        prompt: bool,
+    ) -> io::Result<()> {
+        let mut buffer = String::new();
+        loop {
+            if prompt {
+                write!(out, "{}", if buffer.is_empty() { "sql> " } else { "...> " })?;
+                out.flush()?;
+            }
+
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                break;
+            }
+
+            if buffer.is_empty() {
+                let command = line.trim();
+                if command.is_empty() {
+                    continue;
+                }
+                if command.starts_with('.') {
+                    self.record(command);
+                    match MetaCommand::parse(command) {
+                        Ok(MetaCommand::Quit) => return Ok(()),
+                        Ok(command) => self.run_command(command, out)?,
+                        Err(e) => writeln!(out, "error: {}", e)?,
+                    }
+                    continue;
+                }
+            }
+
+            buffer.push_str(&line);
+            if is_complete(&buffer) {
+                let sql = std::mem::take(&mut buffer);
+                self.record(sql.trim());
+                self.execute(&sql, out)?;
+            }
+        }
+
+        if !buffer.trim().is_empty() {
+            self.record(buffer.trim());
+            self.execute(&buffer, out)?;
+        }
+        Ok(())
+    }
+
+    /// Adds an entry to the history, appending it to the history file. Once the history holds
+    /// `MAX_HISTORY` entries, the oldest one is dropped and the file is rewritten without it.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The statement or command, whose lines are joined into one.
+    fn record(&mut self, entry: &str) {
+        let entry = entry.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+        self.history.push(entry);
+        let excess = self.history.len().saturating_sub(MAX_HISTORY);
+        self.history.drain(..excess);
+
+        if let Some(path) = &self.history_path {
+            // The history is a convenience, so failing to keep it is not an error.
+            if excess > 0 {
+                write_history(path, &self.history);
+            } else if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+                let _ = writeln!(file, "{}", self.history[self.history.len() - 1]);
+            }
+        }
+    }
+
+    /// Runs SQL statements, printing their results, or the first error.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The statements, separated by `;`.
+    /// * `out` - The writer to print to.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or an I/O error.
+    fn execute<W: Write>(&mut self, sql: &str, out: &mut W) -> io::Result<()> {
+        let statements = match self.connection.prepare_all(sql) {
+            Ok(statements) => statements,
+            Err(e) => return writeln!(out, "error: {}", e),
+        };
+
+        for statement in statements {
+            let start = Instant::now();
+            match statement.execute(&mut self.connection) {
+                Ok(outcome) => print_outcome(outcome, self.format, out)?,
+                Err(e) => return writeln!(out, "error: {}", e),
+            }
+            if self.timer {
+                let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+                writeln!(out, "Run time: {:.3} ms", elapsed)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs a meta-command other than `.quit`.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command.
+    /// * `out` - The writer to print to.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or an I/O error.
+    fn run_command<W: Write>(&mut self, command: MetaCommand, out: &mut W) -> io::Result<()> {
+        match command {
+            MetaCommand::Tables => {
+                for table_name in self.table_names() {
+                    writeln!(out, "{}", table_name)?;
+                }
+            }
+            MetaCommand::Schema(Some(table_name)) => {
+                match self.connection.database().schema(&table_name) {
+                    Some(schema) => writeln!(out, "{}", create_table_sql(&table_name, &schema))?,
+                    None => writeln!(out, "error: Table {} does not exist", table_name)?,
+                }
+            }
+            MetaCommand::Schema(None) => {
+                for table_name in self.table_names() {
+                    if let Some(schema) = self.connection.database().schema(&table_name) {
+                        writeln!(out, "{}", create_table_sql(&table_name, &schema))?;
+                    }
+                }
+            }
+            MetaCommand::Open(path) => {
+                match open_database(&path, self.strict, self.discard_malformed) {
+                    Ok(connection) => self.connection = connection,
+                    Err(e) => writeln!(out, "error: {}", e)?,
+                }
+            }
+            MetaCommand::Mode(None) => writeln!(out, "{}", self.format)?,
+            MetaCommand::Mode(Some(format)) => self.format = format,
+            MetaCommand::Timer(timer) => self.timer = timer,
+            MetaCommand::History => {
+                for (number, entry) in self.history.iter().enumerate() {
+                    writeln!(out, "{:5}  {}", number + 1, entry)?;
+                }
+            }
+            MetaCommand::Help => write!(out, "{}", HELP)?,
+            MetaCommand::Quit => {}
+        }
+        Ok(())
+    }
+
+    /// Lists the tables of the database and its table providers, followed by those of the
+    /// attached databases qualified by their names.
+    fn table_names(&self) -> Vec<String> {
+        let database = self.connection.database();
+        let names = |database: &Database| {
+            let mut names: Vec<String> = database
+                .tables
+                .keys()
+                .chain(database.providers.keys())
+                .cloned()
+                .collect();
+            names.sort();
+            names
+        };
+        let attached = database
+            .attached
+            .iter()
+            .flat_map(|(database_name, database)| {
+                names(database)
+                    .into_iter()
+                    .map(move |table_name| format!("{}.{}", database_name, table_name))
+            });
+        names(database).into_iter().chain(attached).collect()
+    }
+}
+
+/// Checks whether the input ends with a `;` that ends a statement, rather than one inside a
+/// string.
+///
+/// # Arguments
+///
+/// * `sql` - The input read so far.
+fn is_complete(sql: &str) -> bool {
+    let mut in_string = false;
+    let mut last = None;
+    for c in sql.chars() {
+        if c == '\'' {
+            in_string = !in_string;
+        }
+        if !c.is_whitespace() {
+            last = Some(c);
+        }
+    }
+    !in_string && last == Some(';')
+}
+
+/// Reads the history file, keeping its last `MAX_HISTORY` entries. A longer file, written by
+/// an earlier version or by several shells at once, is rewritten with only those entries.
+///
+/// # Arguments
+///
+/// * `path` - The history file.
+///
+/// # Returns
+///
+/// The entries, oldest first, or none if the file cannot be read.
+fn read_history(path: &Path) -> Vec<String> {
+    let Ok(history) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<String> = history.lines().map(str::to_string).collect();
+    if entries.len() > MAX_HISTORY {
+        entries.drain(..entries.len() - MAX_HISTORY);
+        write_history(path, &entries);
+    }
+    entries
+}
+
+/// Replaces the contents of the history file, ignoring failures as the history is a
+/// convenience.
+///
+/// # Arguments
+///
+/// * `path` - The history file.
+/// * `entries` - The entries to keep, oldest first.
+fn write_history(path: &Path, entries: &[String]) {
+    let contents: String = entries.iter().map(|entry| format!("{}\n", entry)).collect();
+    let _ = fs::write(path, contents);
+}
+
+/// Writes the schema of a table as the `CREATE TABLE` statement that creates it.
+///
+/// # Arguments
+///
+/// * `table_name` - The name of the table.
+/// * `schema` - The schema of the table.
+fn create_table_sql(table_name: &str, schema: &Schema) -> String {
+    let key_column = schema.key_column();
+    let mut columns = vec![format!("{} INTEGER PRIMARY KEY", key_column)];
+    for column in &schema.columns {
+        let mut definition = format!("{} {}", column.name, column.data_type);
+        if column.not_null {
+            definition.push_str(" NOT NULL");
+        }
+        match &column.default {
+            Some(Value::String(s)) => {
+                definition.push_str(&format!(" DEFAULT '{}'", s.replace('\'', "''")))
+            }
+            Some(Value::Null) => definition.push_str(" DEFAULT NULL"),
+            Some(value) => definition.push_str(&format!(" DEFAULT {}", value)),
+            None => {}
+        }
+        columns.push(definition);
+    }
+    format!("CREATE TABLE {} ({});", table_name, columns.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simple_sql_engine::database::{Database, Table};
+
+    fn repl() -> Repl {
+        let mut database = Database::new();
+        let mut table = Table::new();
+        table
+            .add_row(1, [("title".to_string(), Value::from("Heat"))].into())
+            .unwrap();
+        database.tables.insert("movies".to_string(), table);
+        Repl {
+            connection: Connection::from(database),
+            strict: false,
+            discard_malformed: false,
+            format: Format::Csv,
+            timer: false,
+            history: Vec::new(),
+            history_path: None,
+        }
+    }
+
+    fn run(repl: &mut Repl, input: &str) -> String {
+        let mut out = Vec::new();
+        repl.run(input.as_bytes(), &mut out, false).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_history_limit() {
+        let path = env::temp_dir().join(format!("simple_sql_history_{}", std::process::id()));
+        let entries: Vec<String> = (0..MAX_HISTORY + 5)
+            .map(|i| format!(".mode {}", i))
+            .collect();
+        fs::write(&path, entries.join("\n")).unwrap();
+
+        let history = read_history(&path);
+        assert_eq!(history, entries[5..]);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap().lines().count(),
+            MAX_HISTORY
+        );
+
+        let mut repl = repl();
+        repl.history = history;
+        repl.history_path = Some(path.clone());
+        run(&mut repl, ".tables\n");
+        assert_eq!(repl.history.len(), MAX_HISTORY);
+        assert_eq!(repl.history[0], ".mode 6");
+        assert_eq!(read_history(&path), repl.history);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_is_complete() {
+        assert!(is_complete("SELECT movies.title FROM movies;\n"));
+        assert!(is_complete("SELECT movies.title\nFROM movies ;  "));
+        assert!(!is_complete("SELECT movies.title\n"));
+        assert!(!is_complete("INSERT INTO movies (title) VALUES ('a;\n"));
+        assert!(is_complete("INSERT INTO movies (title) VALUES ('it''s;');"));
+    }
+
+    #[test]
+    fn test_parse_meta_command() {
+        assert_eq!(MetaCommand::parse(".tables"), Ok(MetaCommand::Tables));
+        assert_eq!(MetaCommand::parse(".schema"), Ok(MetaCommand::Schema(None)));
+        assert_eq!(
+            MetaCommand::parse(".schema  movies "),
+            Ok(MetaCommand::Schema(Some("movies".to_string())))
+        );
+        assert_eq!(
+            MetaCommand::parse(".open database/music_data.json"),
+            Ok(MetaCommand::Open("database/music_data.json".to_string()))
+        );
+        assert_eq!(
+            MetaCommand::parse(".mode tsv"),
+            Ok(MetaCommand::Mode(Some(Format::Tsv)))
+        );
+        assert_eq!(
+            MetaCommand::parse(".timer on"),
+            Ok(MetaCommand::Timer(true))
+        );
+        assert_eq!(MetaCommand::parse(".exit"), Ok(MetaCommand::Quit));
+
+        for line in [
+            ".open",
+            ".mode xml",
+            ".timer",
+            ".tables movies",
+            ".schema a b",
+            ".drop",
+        ] {
+            assert!(MetaCommand::parse(line).is_err(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let mut repl = repl();
+        let out = run(
+            &mut repl,
+            "INSERT INTO movies (title)\nVALUES ('Up; Down');\n\n\
+             SELECT movies.title\nFROM movies\nWHERE movies.id > 1;\n\
+             SELECT movies.year FROM nowhere;\n\
+             .mode\n.tables\n.schema movies\n.bogus\n",
+        );
+        assert_eq!(
+            out,
+            "1 row(s) affected\n\
+             movies.title\r\nUp; Down\r\n\
+             error: Table nowhere does not exist\n\
+             csv\nmovies\n\
+             CREATE TABLE movies (id INTEGER PRIMARY KEY, title TEXT NOT NULL);\n\
+             error: Unknown command .bogus, see .help\n"
+        );
+        assert_eq!(repl.history.len(), 7);
+        assert_eq!(
+            repl.history[1],
+            "SELECT movies.title FROM movies WHERE movies.id > 1;"
+        );
+
+        // Input after `.quit` is not read, and a statement left without `;` runs at the end.
+        let out = run(&mut repl, "SELECT movies.title FROM movies\n");
+        assert_eq!(out, "movies.title\r\nHeat\r\nUp; Down\r\n");
+        assert_eq!(
+            run(&mut repl, ".quit\nSELECT movies.title FROM movies;\n"),
+            ""
+        );
+    }
+}
@@ -0,0 +1,28 @@
+//! Helpers shared by the tests of the modules.
+
+use std::path::PathBuf;
+
+/// Gives a path in the temporary directory that no other test or test run uses.
+///
+/// # Arguments
+///
+/// * `name` - The name of the file or directory, unique among the tests.
+pub(crate) fn temporary_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("simple_sql_engine_{}_{}", std::process::id(), name))
+}
+
+/// Copies a database file to a temporary location, so tests can change it.
+///
+/// # Arguments
+///
+/// * `file_path` - The path of the database file.
+/// * `name` - The name of the copy, unique among the tests.
+///
+/// # Returns
+///
+/// The path of the copy.
+pub(crate) fn temporary_copy(file_path: &str, name: &str) -> String {
+    let path = temporary_path(&format!("{}.json", name));
+    std::fs::copy(file_path, &path).unwrap();
+    path.to_str().unwrap().to_string()
+}